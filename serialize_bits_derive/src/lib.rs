@@ -0,0 +1,189 @@
+//! Derive macros for the `SerializerData` and `DeserializerData` traits of `serialize_bits`.
+//!
+//! Fields are written in declaration order. Enums write a `u8` tag (1 for the first variant,
+//! 2 for the second, ...) followed by the fields of the variant.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Generics, Ident, Index,
+};
+
+/// Derive `serialize_bits::ser::SerializerData`.
+#[proc_macro_derive(SerializerData)]
+pub fn derive_serializer_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_serializer(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `serialize_bits::des::DeserializerData`.
+#[proc_macro_derive(DeserializerData)]
+pub fn derive_deserializer_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_deserializer(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn add_bound(mut generics: Generics, bound: TokenStream2) -> Generics {
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    generics
+}
+
+/// Local names used to bind the fields, they can not conflict with the parameters.
+fn field_names(fields: &Fields) -> Vec<Ident> {
+    (0..fields.len())
+        .map(|i| format_ident!("__field{}", i))
+        .collect()
+}
+
+/// Pattern matching all the fields of a variant (or a struct) with the local names.
+fn fields_pattern(fields: &Fields, names: &[Ident]) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let members = named.named.iter().map(|f| &f.ident);
+            quote!({ #(#members: #names),* })
+        }
+        Fields::Unnamed(_) => quote!((#(#names),*)),
+        Fields::Unit => quote!(),
+    }
+}
+
+fn tags(count: usize, span: Span) -> syn::Result<Vec<u8>> {
+    (1..=count)
+        .map(|tag| {
+            u8::try_from(tag).map_err(|_| Error::new(span, "too many variants, the tag is a u8"))
+        })
+        .collect()
+}
+
+fn expand_serializer(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bound(
+        input.generics.clone(),
+        quote!(::serialize_bits::ser::SerializerData),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let writes = data.fields.iter().enumerate().map(|(i, f)| {
+                let member = match &f.ident {
+                    Some(ident) => quote!(#ident),
+                    None => {
+                        let index = Index::from(i);
+                        quote!(#index)
+                    }
+                };
+                quote!(::serialize_bits::ser::SerializerData::serialize_into(&self.#member, out)?;)
+            });
+            quote! {
+                #(#writes)*
+                ::std::result::Result::Ok(())
+            }
+        }
+        Data::Enum(data) => {
+            let tags = tags(data.variants.len(), name.span())?;
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let names = field_names(&variant.fields);
+                let pattern = fields_pattern(&variant.fields, &names);
+                quote! {
+                    Self::#ident #pattern => {
+                        ::serialize_bits::ser::SerializerData::serialize_into(&#tag, out)?;
+                        #(::serialize_bits::ser::SerializerData::serialize_into(#names, out)?;)*
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+                ::std::result::Result::Ok(())
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                name.span(),
+                "SerializerData can not be derived for a union",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::serialize_bits::ser::SerializerData for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn serialize_into<__W: ::serialize_bits::ser::BitsWrite + ?Sized>(
+                &self,
+                out: &mut __W,
+            ) -> ::std::io::Result<()> {
+                #body
+            }
+        }
+    })
+}
+
+/// Read each field with the local names, then build the value with the constructor.
+fn deserialize_fields(constructor: TokenStream2, fields: &Fields) -> TokenStream2 {
+    let names = field_names(fields);
+    let pattern = fields_pattern(fields, &names);
+    quote! {
+        #(let (#names, index) = ::serialize_bits::des::DeserializerData::try_from_data(data, index)?;)*
+        ::std::result::Result::Ok((#constructor #pattern, index))
+    }
+}
+
+fn expand_deserializer(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bound(
+        input.generics.clone(),
+        quote!(::serialize_bits::des::DeserializerData),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => deserialize_fields(quote!(Self), &data.fields),
+        Data::Enum(data) => {
+            let tags = tags(data.variants.len(), name.span())?;
+            let type_name = name.to_string();
+            let arms = data.variants.iter().zip(tags).map(|(variant, tag)| {
+                let ident = &variant.ident;
+                let read = deserialize_fields(quote!(Self::#ident), &variant.fields);
+                quote!(#tag => { #read })
+            });
+            quote! {
+                let (tag, index) = <u8 as ::serialize_bits::des::DeserializerData>::try_from_data(data, index)?;
+                match tag {
+                    #(#arms)*
+                    tag => ::std::result::Result::Err(::serialize_bits::des::DeserializeError::InvalidTag {
+                        type_name: #type_name,
+                        tag,
+                    }),
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                name.span(),
+                "DeserializerData can not be derived for a union",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::serialize_bits::des::DeserializerData for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn try_from_data<__R: ::serialize_bits::des::BitsRead + ?Sized>(
+                data: &__R,
+                index: usize,
+            ) -> ::std::result::Result<(Self, usize), ::serialize_bits::des::DeserializeError> {
+                #body
+            }
+        }
+    })
+}
@@ -0,0 +1,154 @@
+use serialize_bits::{
+    des::{DeserializeError, DeserializerData},
+    ser::SerializerData,
+};
+
+#[derive(Clone, Debug, PartialEq, SerializerData, DeserializerData)]
+struct City {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+struct Address {
+    id: i64,
+    street: String,
+    postal_code: String,
+    city: City,
+}
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+enum Genre {
+    Male,
+    Female,
+    Custom(String),
+}
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+struct Person {
+    id: i32,
+    name: String,
+    genre: Genre,
+    addresses: Vec<Address>,
+}
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+struct Point(i16, i16);
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+struct Marker;
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+enum Shape {
+    Empty,
+    Circle(Point, u16),
+    Rect {
+        origin: Point,
+        width: u16,
+        height: u16,
+    },
+}
+
+#[derive(Debug, PartialEq, SerializerData, DeserializerData)]
+struct Wrapper<T> {
+    data: T,
+    index: Option<T>,
+}
+
+#[test]
+fn test_struct() {
+    let city = City {
+        id: 44,
+        name: String::from("NANTES"),
+    };
+    let address1 = Address {
+        id: 1,
+        street: String::from("avenue du Général de Gaulle"),
+        postal_code: String::from("44000"),
+        city: city.clone(),
+    };
+    let address2 = Address {
+        id: 2,
+        street: String::from("rue Jean Jaurès"),
+        postal_code: String::from("44000"),
+        city: city.clone(),
+    };
+    let person = Person {
+        id: 35,
+        name: String::from("Albert"),
+        genre: Genre::Custom(String::from("Doctor")),
+        addresses: vec![address1, address2],
+    };
+    let data = person.to_data();
+    // Same bytes as the hand-written implementation in tests/custom.rs.
+    assert_eq!(
+        vec![
+            35, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 65, 108, 98, 101, 114, 116, 3, 6, 0, 0, 0, 0, 0,
+            0, 0, 68, 111, 99, 116, 111, 114, 147, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 29,
+            0, 0, 0, 0, 0, 0, 0, 97, 118, 101, 110, 117, 101, 32, 100, 117, 32, 71, 195, 169, 110,
+            195, 169, 114, 97, 108, 32, 100, 101, 32, 71, 97, 117, 108, 108, 101, 5, 0, 0, 0, 0, 0,
+            0, 0, 52, 52, 48, 48, 48, 44, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 78, 65, 78,
+            84, 69, 83, 2, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 114, 117, 101, 32, 74,
+            101, 97, 110, 32, 74, 97, 117, 114, 195, 168, 115, 5, 0, 0, 0, 0, 0, 0, 0, 52, 52, 48,
+            48, 48, 44, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 78, 65, 78, 84, 69, 83
+        ],
+        data
+    );
+    assert_eq!((person, 188), Person::from_data(&data, 0));
+}
+
+#[test]
+fn test_tuple_struct() {
+    let value = Point(-2, 300);
+    let data = value.to_data();
+    assert_eq!(vec![254, 255, 44, 1], data);
+    assert_eq!((value, 4), Point::from_data(&data, 0));
+}
+
+#[test]
+fn test_unit_struct() {
+    let data = Marker.to_data();
+    assert!(data.is_empty());
+    assert_eq!((Marker, 0), Marker::from_data(&data, 0));
+}
+
+#[test]
+fn test_enum() {
+    let values = vec![
+        Shape::Empty,
+        Shape::Circle(Point(1, 2), 3),
+        Shape::Rect {
+            origin: Point(4, 5),
+            width: 6,
+            height: 7,
+        },
+    ];
+    let data = values.to_data();
+    assert_eq!(
+        vec![17, 0, 0, 0, 0, 0, 0, 0, 1, 2, 1, 0, 2, 0, 3, 0, 3, 4, 0, 5, 0, 6, 0, 7, 0],
+        data
+    );
+    assert_eq!((values, 25), Vec::from_data(&data, 0));
+}
+
+#[test]
+fn test_enum_invalid_tag() {
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Shape",
+            tag: 4,
+        }),
+        Shape::try_from_data(&[4], 0)
+    );
+}
+
+#[test]
+fn test_generic() {
+    let value = Wrapper {
+        data: 7_u8,
+        index: Some(9),
+    };
+    let data = value.to_data();
+    assert_eq!(vec![7, 1, 9], data);
+    assert_eq!((value, 3), Wrapper::from_data(&data, 0));
+}
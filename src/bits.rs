@@ -0,0 +1,251 @@
+use std::io;
+
+use crate::{des::DeserializeError, ser::BitsWrite};
+
+/// # BitOrder
+///
+/// Order of the bits in each byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitOrder {
+    /// The first bit written is the most significant bit of the byte,
+    /// the values are written from their most significant bit (default).
+    #[default]
+    MsbFirst,
+    /// The first bit written is the least significant bit of the byte,
+    /// the values are written from their least significant bit.
+    LsbFirst,
+}
+
+/// # BitWriter
+///
+/// Pack values on the exact number of bits, the last byte is padded with zeros.
+///
+/// Example :
+///
+/// ```
+/// use serialize_bits::bits::{BitOrder, BitWriter};
+///
+/// let mut writer = BitWriter::new(BitOrder::MsbFirst);
+/// writer.write_bits(5, 3).unwrap();
+/// writer.write_bits(0xABC, 12).unwrap();
+/// writer.write_bool(true);
+/// assert_eq!(vec![0xB5, 0x79], writer.into_bytes());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    bit_len: usize,
+    order: BitOrder,
+}
+
+impl BitWriter {
+    /// Create an empty writer.
+    pub fn new(order: BitOrder) -> Self {
+        Self {
+            bytes: Vec::new(),
+            bit_len: 0,
+            order,
+        }
+    }
+
+    fn push_bit(&mut self, bit: bool) {
+        let offset = self.bit_len % 8;
+        if offset == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            let last = self.bytes.len() - 1;
+            match self.order {
+                BitOrder::MsbFirst => self.bytes[last] |= 0x80 >> offset,
+                BitOrder::LsbFirst => self.bytes[last] |= 1 << offset,
+            }
+        }
+        self.bit_len += 1;
+    }
+
+    /// Write the unsigned value on `count` bits.
+    ///
+    /// Returns an error if the value does not fit in `count` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is greater than 64.
+    pub fn write_bits(&mut self, value: u64, count: u32) -> io::Result<()> {
+        assert!(
+            count <= 64,
+            "can not write more than 64 bits, count={count}"
+        );
+        if count < 64 && value >> count != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the value {value} does not fit in {count} bits"),
+            ));
+        }
+        match self.order {
+            BitOrder::MsbFirst => {
+                for i in (0..count).rev() {
+                    self.push_bit((value >> i) & 1 == 1);
+                }
+            }
+            BitOrder::LsbFirst => {
+                for i in 0..count {
+                    self.push_bit((value >> i) & 1 == 1);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the signed value on `count` bits (two's complement).
+    ///
+    /// Returns an error if the value does not fit in `count` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is greater than 64.
+    pub fn write_signed(&mut self, value: i64, count: u32) -> io::Result<()> {
+        assert!(
+            count <= 64,
+            "can not write more than 64 bits, count={count}"
+        );
+        let fits = match count {
+            0 => value == 0,
+            64 => true,
+            _ => {
+                let half = 1i64 << (count - 1);
+                (-half..half).contains(&value)
+            }
+        };
+        if !fits {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the value {value} does not fit in {count} bits"),
+            ));
+        }
+        let mask = if count == 64 {
+            u64::MAX
+        } else {
+            (1 << count) - 1
+        };
+        self.write_bits(value as u64 & mask, count)
+    }
+
+    /// Write the bool on 1 bit.
+    pub fn write_bool(&mut self, value: bool) {
+        self.push_bit(value);
+    }
+
+    /// Number of bits written.
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Return the bytes, the last byte is padded with zeros.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Write the bytes into the destination, to continue with the byte API.
+    pub fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        out.write_bytes(&self.bytes)
+    }
+}
+
+/// # BitReader
+///
+/// Read values packed with a [`BitWriter`] using the same [`BitOrder`].
+///
+/// The sizes of the `DeserializeError::UnexpectedEof` returned by the reader are in bits.
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    order: BitOrder,
+}
+
+impl<'a> BitReader<'a> {
+    /// Read the data from the first bit of the byte at `index`.
+    pub fn new(data: &'a [u8], index: usize, order: BitOrder) -> Self {
+        Self {
+            data,
+            position: index.saturating_mul(8),
+            order,
+        }
+    }
+
+    fn check(&self, count: u32) -> Result<(), DeserializeError> {
+        let available = (self.data.len() * 8).saturating_sub(self.position);
+        if count as usize > available {
+            return Err(DeserializeError::UnexpectedEof {
+                type_name: "bits",
+                needed: count as usize,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let byte = self.data[self.position / 8];
+        let offset = self.position % 8;
+        self.position += 1;
+        match self.order {
+            BitOrder::MsbFirst => byte & (0x80 >> offset) != 0,
+            BitOrder::LsbFirst => byte & (1 << offset) != 0,
+        }
+    }
+
+    /// Read an unsigned value of `count` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is greater than 64.
+    pub fn read_bits(&mut self, count: u32) -> Result<u64, DeserializeError> {
+        assert!(count <= 64, "can not read more than 64 bits, count={count}");
+        self.check(count)?;
+        let mut value = 0u64;
+        match self.order {
+            BitOrder::MsbFirst => {
+                for _ in 0..count {
+                    value = (value << 1) | u64::from(self.next_bit());
+                }
+            }
+            BitOrder::LsbFirst => {
+                for i in 0..count {
+                    value |= u64::from(self.next_bit()) << i;
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Read a signed value of `count` bits (two's complement).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is greater than 64.
+    pub fn read_signed(&mut self, count: u32) -> Result<i64, DeserializeError> {
+        let value = self.read_bits(count)?;
+        if count == 0 {
+            return Ok(0);
+        }
+        let shift = 64 - count;
+        Ok(((value << shift) as i64) >> shift)
+    }
+
+    /// Read a bool of 1 bit.
+    pub fn read_bool(&mut self) -> Result<bool, DeserializeError> {
+        self.check(1)?;
+        Ok(self.next_bit())
+    }
+
+    /// Number of bits read from the start of the data.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Index of the next whole byte, to continue with the byte API.
+    pub fn byte_index(&self) -> usize {
+        self.position.div_ceil(8)
+    }
+}
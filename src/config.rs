@@ -0,0 +1,170 @@
+use std::sync::atomic::Ordering;
+
+/// # ByteOrder
+///
+/// Order of the bytes of the integers in the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    /// Least significant byte first (default).
+    #[default]
+    LittleEndian,
+    /// Most significant byte first (network order).
+    BigEndian,
+}
+
+/// # IntEncoding
+///
+/// Encoding of the integers larger than a byte, including the sizes of `String` and collections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntEncoding {
+    /// All the bytes of the integer, with the byte order of the config (default).
+    #[default]
+    Fixed,
+    /// LEB128 variable-length integer, 7 bits per byte.
+    ///
+    /// The signed integers are ZigZag encoded first, so small negative values stay short.
+    /// `u8` and `i8` are always written as one byte.
+    Varint,
+}
+
+/// # LengthPrefix
+///
+/// Size written before the elements of the collections (`Vec`, `VecDeque`, sets, maps, ...).
+///
+/// `String`, byte strings and `Vec<u8>` always write their number of bytes, it is the same with both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthPrefix {
+    /// Size in bytes of all the elements (default), as written by the previous versions.
+    #[default]
+    Bytes,
+    /// Number of elements, the decoder preallocates the collection
+    /// and the encoder writes the elements without buffering them (up to 1 048 576 elements,
+    /// beyond they are buffered to check that each one is written with data).
+    ///
+    /// The elements written without data, like `()`, are accepted,
+    /// up to 1 048 576 elements in a collection (an error is returned beyond, when writing
+    /// and when reading).
+    Count,
+}
+
+/// # Config
+///
+/// Options of the encoding, the same config must be used for the serialization
+/// and the deserialization of the data.
+///
+/// The default config writes all the bytes of the integers in little-endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    byte_order: ByteOrder,
+    int_encoding: IntEncoding,
+    canonical_nan: bool,
+    load_ordering: Ordering,
+    length_prefix: LengthPrefix,
+    sorted_keys: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Config {
+    /// Create the default config.
+    pub const fn new() -> Self {
+        Self {
+            byte_order: ByteOrder::LittleEndian,
+            int_encoding: IntEncoding::Fixed,
+            canonical_nan: false,
+            load_ordering: Ordering::SeqCst,
+            length_prefix: LengthPrefix::Bytes,
+            sorted_keys: false,
+        }
+    }
+
+    /// Change the byte order of the integers.
+    pub const fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Byte order of the integers.
+    pub const fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    /// Change the encoding of the integers.
+    pub const fn with_int_encoding(mut self, int_encoding: IntEncoding) -> Self {
+        self.int_encoding = int_encoding;
+        self
+    }
+
+    /// Encoding of the integers.
+    pub const fn int_encoding(&self) -> IntEncoding {
+        self.int_encoding
+    }
+
+    /// Write every NaN `f32` and `f64` with the same bits (quiet NaN without payload),
+    /// so equal values always produce identical bytes.
+    ///
+    /// Disabled by default, the bits of the NaN (sign and payload) are kept.
+    pub const fn with_canonical_nan(mut self, canonical_nan: bool) -> Self {
+        self.canonical_nan = canonical_nan;
+        self
+    }
+
+    /// Write every NaN with the same bits.
+    pub const fn canonical_nan(&self) -> bool {
+        self.canonical_nan
+    }
+
+    /// Change the memory ordering used to load the atomics before writing them (`SeqCst` by default).
+    ///
+    /// `Release` loads like `Relaxed` and `AcqRel` like `Acquire`, a load can not release.
+    pub const fn with_load_ordering(mut self, load_ordering: Ordering) -> Self {
+        self.load_ordering = load_ordering;
+        self
+    }
+
+    /// Memory ordering used to load the atomics, never `Release` or `AcqRel`.
+    pub const fn load_ordering(&self) -> Ordering {
+        match self.load_ordering {
+            Ordering::Release => Ordering::Relaxed,
+            Ordering::AcqRel => Ordering::Acquire,
+            ordering => ordering,
+        }
+    }
+
+    /// Change the size written before the elements of the collections.
+    ///
+    /// The data written with [`LengthPrefix::Bytes`] is read with a config using it,
+    /// then written again with [`LengthPrefix::Count`] to migrate it.
+    pub const fn with_length_prefix(mut self, length_prefix: LengthPrefix) -> Self {
+        self.length_prefix = length_prefix;
+        self
+    }
+
+    /// Size written before the elements of the collections.
+    pub const fn length_prefix(&self) -> LengthPrefix {
+        self.length_prefix
+    }
+
+    /// Write the keys of `HashMap` and the elements of `HashSet` sorted by their encoded bytes,
+    /// so equal collections always produce identical bytes, whatever their hasher.
+    ///
+    /// The keys are sorted by their encoding alone with the config, without a
+    /// [`GraphWriter`](crate::graph::GraphWriter). With a `GraphWriter`, the keys containing
+    /// an `Rc` or an `Arc` are written with the tags and ids of the graph, which depend on
+    /// the objects already written, so the bytes are not guaranteed to be identical.
+    ///
+    /// Disabled by default, the elements are written in the order of the hashes.
+    pub const fn with_sorted_keys(mut self, sorted_keys: bool) -> Self {
+        self.sorted_keys = sorted_keys;
+        self
+    }
+
+    /// Write the keys of the hash-based collections sorted by their encoded bytes.
+    pub const fn sorted_keys(&self) -> bool {
+        self.sorted_keys
+    }
+}
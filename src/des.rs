@@ -1,412 +1,1332 @@
 use std::{
+    borrow::{Cow, ToOwned},
+    cell::{Cell, RefCell},
+    cmp::{Ordering, Reverse},
     collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
-    hash::Hash,
-    net::SocketAddr,
+    error::Error,
+    ffi::{CString, OsString},
+    fmt::{self, Display},
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping,
+    },
+    ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+    path::PathBuf,
+    rc::{self, Rc},
+    sync::{self, atomic, Arc, Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use crate::{
+    config::{ByteOrder, Config, IntEncoding, LengthPrefix},
+    graph::{self, ReadGraph},
+};
+
+/// Derive macro for [`DeserializerData`], reading the fields in declaration order.
+#[cfg(feature = "derive")]
+pub use serialize_bits_derive::DeserializerData;
+
+/// # DeserializeError
+///
+/// Error returned when data can not be converted into a Struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializeError {
+    /// The data ends before the value is complete.
+    UnexpectedEof {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+        /// Number of bytes needed.
+        needed: usize,
+        /// Number of bytes available from the index.
+        available: usize,
+    },
+    /// The bytes of a string are not valid UTF-8.
+    InvalidUtf8 {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+    },
+    /// The text of a [`LegacySocketAddr`] can not be parsed as a socket address.
+    InvalidSocketAddr {
+        /// The decoded text.
+        value: String,
+    },
+    /// The tag (variant code) is unknown for the type.
+    InvalidTag {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+        /// The decoded tag.
+        tag: u8,
+    },
+    /// The varint is longer than the type or not in its shortest form.
+    InvalidVarint {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+    },
+    /// The value does not fit in the type on this architecture.
+    OutOfRange {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+    },
+    /// The bytes of a C string contain a nul.
+    InteriorNul {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+        /// Position of the nul in the bytes.
+        position: usize,
+    },
+    /// The value of a non-zero integer is zero.
+    Zero {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+    },
+    /// An element of a collection is read without data, so the size can not end the collection.
+    EmptyElement {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+    },
+    /// The id of a shared object is unknown, of another type, or of an object containing it.
+    InvalidReference {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+        /// The decoded id.
+        id: usize,
+    },
+    /// The type can only be read by a [`GraphReader`](crate::graph::GraphReader).
+    GraphRequired {
+        /// Name of the type being deserialized.
+        type_name: &'static str,
+    },
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof {
+                type_name,
+                needed,
+                available,
+            } => write!(
+                f,
+                "Error when deserialize {type_name}, unexpected end of data (needed={needed}, available={available})"
+            ),
+            Self::InvalidUtf8 { type_name } => {
+                write!(f, "Error when deserialize {type_name}, invalid UTF-8")
+            }
+            Self::InvalidSocketAddr { value } => {
+                write!(f, "Error when deserialize SocketAddr, invalid address {value:?}")
+            }
+            Self::InvalidTag { type_name, tag } => {
+                write!(f, "Error when deserialize {type_name}, invalid tag {tag}")
+            }
+            Self::InvalidVarint { type_name } => {
+                write!(f, "Error when deserialize {type_name}, invalid varint")
+            }
+            Self::OutOfRange { type_name } => {
+                write!(f, "Error when deserialize {type_name}, value out of range")
+            }
+            Self::InteriorNul {
+                type_name,
+                position,
+            } => write!(
+                f,
+                "Error when deserialize {type_name}, nul byte at position {position}"
+            ),
+            Self::Zero { type_name } => {
+                write!(f, "Error when deserialize {type_name}, the value is zero")
+            }
+            Self::EmptyElement { type_name } => {
+                write!(f, "Error when deserialize {type_name}, element without data")
+            }
+            Self::InvalidReference { type_name, id } => {
+                write!(f, "Error when deserialize {type_name}, invalid reference {id}")
+            }
+            Self::GraphRequired { type_name } => {
+                write!(f, "Error when deserialize {type_name}, a GraphReader is required")
+            }
+        }
+    }
+}
+
+impl Error for DeserializeError {}
+
+/// # BitsRead
+///
+/// Source of the data to deserialize.
+///
+/// Implemented for `[u8]`, `[u8; N]` and `Vec<u8>` with the default config.
+pub trait BitsRead {
+    /// All the bytes of the source.
+    fn bytes(&self) -> &[u8];
+
+    /// Options of the encoding, the default config if not overridden.
+    fn config(&self) -> Config {
+        Config::default()
+    }
+
+    /// Objects already read, only a [`GraphReader`](crate::graph::GraphReader) keeps them.
+    fn graph(&self) -> Option<&ReadGraph> {
+        None
+    }
+}
+
+impl BitsRead for [u8] {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const N: usize> BitsRead for [u8; N] {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+impl BitsRead for Vec<u8> {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+/// # ConfigReader
+///
+/// Source of data written with a specific [`Config`].
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigReader<'a> {
+    data: &'a [u8],
+    config: Config,
+    graph: Option<&'a ReadGraph>,
+}
+
+impl<'a> ConfigReader<'a> {
+    /// Read the data with the config.
+    pub fn new(data: &'a [u8], config: Config) -> Self {
+        Self {
+            data,
+            config,
+            graph: None,
+        }
+    }
+
+    /// Read the data until `end` with the config and the graph of the source.
+    ///
+    /// Not generic over the source, so recursive types use the same reader at every level.
+    fn limited<R: BitsRead + ?Sized>(data: &'a R, end: usize) -> Self {
+        Self {
+            data: &data.bytes()[..end],
+            config: data.config(),
+            graph: data.graph(),
+        }
+    }
+}
+
+impl BitsRead for ConfigReader<'_> {
+    fn bytes(&self) -> &[u8] {
+        self.data
+    }
+
+    fn config(&self) -> Config {
+        self.config
+    }
+
+    fn graph(&self) -> Option<&ReadGraph> {
+        self.graph
+    }
+}
+
 /// # DeserializerData
 ///
 /// Trait for convert data to a Struct.
-/// 
+///
 /// The library already implements the trait for :
 /// - u8, u16, u32, u64, u128, usize
 /// - i8, i16, i32, i64, i128, isize
+/// - NonZeroU8, ..., NonZeroU128, NonZeroUsize, NonZeroI8, ..., NonZeroI128, NonZeroIsize
+/// - f32, f64
+/// - AtomicBool, AtomicU8, ..., AtomicU64, AtomicUsize, AtomicI8, ..., AtomicI64, AtomicIsize
+/// - Duration, SystemTime
 /// - char
 /// - bool
 /// - String
-/// - Option<T>
-/// - SocketAddr
+/// - OsString, PathBuf, CString
+/// - Option<T>, Result<T, E>
+/// - (), PhantomData<T>
+/// - Range<T>, RangeInclusive<T>, RangeFrom<T>, RangeTo<T>, RangeToInclusive<T>, RangeFull
+/// - Bound<T>
+/// - Ordering, Reverse<T>, Wrapping<T>, Saturating<T>
+/// - IpAddr, Ipv4Addr, Ipv6Addr
+/// - SocketAddr, SocketAddrV4, SocketAddrV6, LegacySocketAddr
+/// - (T1, ..., T16) tuples
+/// - [T; N] arrays
+/// - Box<T>, Box<str>, Box<[T]>, Rc<T>, Rc<str>, Rc<[T]>, Arc<T>, Arc<str>, Arc<[T]>, Cow<T>
+/// - rc::Weak<T>, sync::Weak<T> (with a GraphReader)
+/// - Cell<T>, RefCell<T>, Mutex<T>, RwLock<T>
 /// - Vec<T>, VecDeque<T>, LinkedList<T>
-/// - HashSet<T>, BTreeSet<T>
+/// - HashSet<T, S>, BTreeSet<T>
 /// - BinaryHeap<T>
-/// - HashMap<K, V>, BTreeMap<K, V>
+/// - HashMap<K, V, S>, BTreeMap<K, V>
 pub trait DeserializerData {
-    /// Convert bits (Vec<u8>) into Struct with the next index for convert another Struct.
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    /// Convert bits into Struct with the next index for convert another Struct.
+    ///
+    /// Returns an error if the data is truncated or invalid.
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized;
+
+    /// Convert bits into Struct with the next index for convert another Struct.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is truncated or invalid, see [`DeserializerData::try_from_data`].
+    fn from_data<R: BitsRead + ?Sized>(data: &R, index: usize) -> (Self, usize)
+    where
+        Self: Sized,
+    {
+        match Self::try_from_data(data, index) {
+            Ok(res) => res,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Convert bits into Struct with the next index, the data was written with the config.
+    fn try_from_data_with(
+        data: &[u8],
+        index: usize,
+        config: Config,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        Self::try_from_data(&ConfigReader::new(data, config), index)
+    }
+
+    /// Convert bits into a Vec of Struct, the size of the elements followed by each element.
+    ///
+    /// The primitives read all the elements at once, the other types do not override it.
+    fn deserialize_vec<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Vec<Self>, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        deserialize_elements(data, index)
+    }
 }
 
-fn sub(struct_name: &str, data: &Vec<u8>, index: usize, size: usize) -> Vec<u8> {
-    if index + size <= data.len() {
-        data[index..index + size].to_vec()
-    } else {
-        panic!("Error when deserialize {struct_name}, index too large {} > {}, (index={index}, size={size})", index + size, data.len())
+/// # BorrowDeserializerData
+///
+/// Trait for convert data to a Struct borrowing the strings and the bytes of the data (no copy).
+///
+/// The data is read like the owned types (`&str` like `String`, `&[u8]` like `Vec<u8>`), so the
+/// fields of a Struct can be read with both traits from the same data.
+/// A [`ConfigReader`] is used to read data written with a config.
+///
+/// The library already implements the trait for :
+/// - &str, &[u8]
+/// - Cow<str>, Cow<[u8]> (always `Cow::Borrowed`)
+/// - Option<T>
+///
+/// Example :
+///
+/// ```
+/// use serialize_bits::{des::{BitsRead, BorrowDeserializerData, DeserializeError, DeserializerData}, ser::SerializerData};
+///
+/// struct Entry<'a> {
+///     id: u32,
+///     name: &'a str,
+/// }
+///
+/// impl<'de> BorrowDeserializerData<'de> for Entry<'de> {
+///     fn try_borrow_from_data<R: BitsRead + ?Sized>(
+///         data: &'de R,
+///         index: usize,
+///     ) -> Result<(Self, usize), DeserializeError> {
+///         let (id, index) = u32::try_from_data(data, index)?;
+///         let (name, index) = <&str>::try_borrow_from_data(data, index)?;
+///         Ok((Self { id, name }, index))
+///     }
+/// }
+///
+/// let data = (7_u32, String::from("seven")).to_data();
+/// let (entry, _) = Entry::try_borrow_from_data(&data, 0).unwrap();
+/// assert_eq!((7, "seven"), (entry.id, entry.name));
+/// ```
+pub trait BorrowDeserializerData<'de>: Sized {
+    /// Convert bits into Struct borrowing the data, with the next index for convert another Struct.
+    ///
+    /// Returns an error if the data is truncated or invalid.
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>;
+
+    /// Convert bits into Struct borrowing the data, with the next index for convert another Struct.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is truncated or invalid, see [`BorrowDeserializerData::try_borrow_from_data`].
+    fn borrow_from_data<R: BitsRead + ?Sized>(data: &'de R, index: usize) -> (Self, usize) {
+        match Self::try_borrow_from_data(data, index) {
+            Ok(res) => res,
+            Err(e) => panic!("{e}"),
+        }
     }
 }
 
-#[cfg(target_pointer_width = "64")]
-impl DeserializerData for usize {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
-    where
-        Self: Sized,
-    {
-        (
-            usize::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-                data[index + 4],
-                data[index + 5],
-                data[index + 6],
-                data[index + 7],
-            ]),
-            index + 8,
-        )
+fn check(
+    type_name: &'static str,
+    data: &[u8],
+    index: usize,
+    size: usize,
+) -> Result<usize, DeserializeError> {
+    match index.checked_add(size) {
+        Some(end) if end <= data.len() => Ok(end),
+        _ => Err(DeserializeError::UnexpectedEof {
+            type_name,
+            needed: size,
+            available: data.len().saturating_sub(index),
+        }),
+    }
+}
+
+fn sub<'a>(
+    type_name: &'static str,
+    data: &'a [u8],
+    index: usize,
+    size: usize,
+) -> Result<&'a [u8], DeserializeError> {
+    let end = check(type_name, data, index, size)?;
+    Ok(&data[index..end])
+}
+
+fn read<const N: usize>(
+    type_name: &'static str,
+    data: &[u8],
+    index: usize,
+) -> Result<([u8; N], usize), DeserializeError> {
+    let end = check(type_name, data, index, N)?;
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(&data[index..end]);
+    Ok((bytes, end))
+}
+
+/// Maximum memory in bytes preallocated from a number of elements read in the data,
+/// so an invalid number can not allocate too much before the elements are read.
+const MAX_PREALLOCATION: usize = 1 << 20;
+
+/// Maximum number of elements of a collection when an element is read without data,
+/// so an invalid number can not read the same element almost forever.
+pub(crate) const MAX_EMPTY_ELEMENTS: usize = 1 << 20;
+
+/// Capacity to preallocate for `count` elements of `T`, with `available` bytes left in the data.
+fn capacity<T>(count: usize, available: usize) -> usize {
+    count
+        .min(available)
+        .min(MAX_PREALLOCATION / std::mem::size_of::<T>().max(1))
+}
+
+/// Read the size of the elements, then each element in place until the end of the elements.
+///
+/// With [`LengthPrefix::Count`], read the number of elements, then preallocate and read each element,
+/// at most [`MAX_EMPTY_ELEMENTS`] if an element is read without data.
+fn deserialize_sized<T: DeserializerData, C>(
+    type_name: &'static str,
+    data: &(impl BitsRead + ?Sized),
+    index: usize,
+    with_capacity: impl FnOnce(usize) -> C,
+    mut push: impl FnMut(&mut C, T),
+) -> Result<(C, usize), DeserializeError> {
+    if data.config().length_prefix() == LengthPrefix::Count {
+        let (count, mut index) = usize::try_from_data(data, index)?;
+        let available = data.bytes().len().saturating_sub(index);
+        let mut res = with_capacity(capacity::<T>(count, available));
+        for _ in 0..count {
+            let (e, e_index) = T::try_from_data(data, index)?;
+            if e_index == index && count > MAX_EMPTY_ELEMENTS {
+                return Err(DeserializeError::EmptyElement { type_name });
+            }
+            push(&mut res, e);
+            index = e_index;
+        }
+        return Ok((res, index));
+    }
+    let (size, index) = usize::try_from_data(data, index)?;
+    let end = check(type_name, data.bytes(), index, size)?;
+    let list = ConfigReader::limited(data, end);
+    let mut res = with_capacity(0);
+    let mut index = index;
+    while index < end {
+        let (e, e_index) = T::try_from_data(&list, index)?;
+        if e_index == index {
+            return Err(DeserializeError::EmptyElement { type_name });
+        }
+        push(&mut res, e);
+        index = e_index;
+    }
+    Ok((res, end))
+}
+
+/// Read the size of the entries, then each key and value in place until the end of the entries.
+///
+/// With [`LengthPrefix::Count`], read the number of entries, then preallocate and read each entry,
+/// at most [`MAX_EMPTY_ELEMENTS`] if an entry is read without data.
+fn deserialize_sized_map<K: DeserializerData, V: DeserializerData, C>(
+    type_name: &'static str,
+    data: &(impl BitsRead + ?Sized),
+    index: usize,
+    with_capacity: impl FnOnce(usize) -> C,
+    mut insert: impl FnMut(&mut C, K, V),
+) -> Result<(C, usize), DeserializeError> {
+    if data.config().length_prefix() == LengthPrefix::Count {
+        let (count, mut index) = usize::try_from_data(data, index)?;
+        let available = data.bytes().len().saturating_sub(index);
+        let mut res = with_capacity(capacity::<(K, V)>(count, available));
+        for _ in 0..count {
+            let (key, e_index) = K::try_from_data(data, index)?;
+            let (value, e_index) = V::try_from_data(data, e_index)?;
+            if e_index == index && count > MAX_EMPTY_ELEMENTS {
+                return Err(DeserializeError::EmptyElement { type_name });
+            }
+            insert(&mut res, key, value);
+            index = e_index;
+        }
+        return Ok((res, index));
+    }
+    let (size, index) = usize::try_from_data(data, index)?;
+    let end = check(type_name, data.bytes(), index, size)?;
+    let list = ConfigReader::limited(data, end);
+    let mut res = with_capacity(0);
+    let mut index = index;
+    while index < end {
+        let (key, e_index) = K::try_from_data(&list, index)?;
+        let (value, e_index) = V::try_from_data(&list, e_index)?;
+        if e_index == index {
+            return Err(DeserializeError::EmptyElement { type_name });
+        }
+        insert(&mut res, key, value);
+        index = e_index;
+    }
+    Ok((res, end))
+}
+
+/// Read the size of the elements, then each element into a Vec.
+fn deserialize_elements<T: DeserializerData, R: BitsRead + ?Sized>(
+    data: &R,
+    index: usize,
+) -> Result<(Vec<T>, usize), DeserializeError> {
+    deserialize_sized("Vec", data, index, Vec::with_capacity, Vec::push)
+}
+
+/// Read the size of the elements, then convert all the elements at once.
+///
+/// With [`LengthPrefix::Count`], the size is the number of elements of `N` bytes.
+fn deserialize_bulk<T, const N: usize, R: BitsRead + ?Sized>(
+    type_name: &'static str,
+    data: &R,
+    index: usize,
+    from_bytes: impl Fn([u8; N]) -> T,
+) -> Result<(Vec<T>, usize), DeserializeError> {
+    let (size, index) = usize::try_from_data(data, index)?;
+    let size = match data.config().length_prefix() {
+        LengthPrefix::Bytes => size,
+        LengthPrefix::Count => size.saturating_mul(N),
+    };
+    let bytes = sub("Vec", data.bytes(), index, size)?;
+    let chunks = bytes.chunks_exact(N);
+    let available = chunks.remainder().len();
+    if available != 0 {
+        return Err(DeserializeError::UnexpectedEof {
+            type_name,
+            needed: N,
+            available,
+        });
     }
+    let res = chunks
+        .map(|chunk| {
+            let mut bytes = [0; N];
+            bytes.copy_from_slice(chunk);
+            from_bytes(bytes)
+        })
+        .collect();
+    Ok((res, index + size))
 }
 
-#[cfg(target_pointer_width = "32")]
+/// Read a LEB128 varint of at most `bits` bits.
+///
+/// The varint is rejected if it has more bytes than needed for the type, if the value does not
+/// fit in `bits` bits or if it ends with a zero byte (not the shortest form).
+fn read_varint(
+    type_name: &'static str,
+    data: &[u8],
+    index: usize,
+    bits: u32,
+) -> Result<(u128, usize), DeserializeError> {
+    let invalid = DeserializeError::InvalidVarint { type_name };
+    let mut value = 0u128;
+    let mut index = index;
+    for i in 0..bits.div_ceil(7) {
+        let ([byte], next) = read(type_name, data, index)?;
+        index = next;
+        let part = u128::from(byte & 0x7f);
+        let shift = 7 * i;
+        if bits - shift < 7 && part >> (bits - shift) != 0 {
+            return Err(invalid);
+        }
+        value |= part << shift;
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(invalid);
+            }
+            return Ok((value, index));
+        }
+    }
+    Err(invalid)
+}
+
+/// Reverse of the ZigZag mapping of the signed values.
+fn unzigzag(value: u128) -> i128 {
+    ((value >> 1) as i128) ^ -((value & 1) as i128)
+}
+
+impl DeserializerData for u8 {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (bytes, index) = read("u8", data.bytes(), index)?;
+        Ok((u8::from_le_bytes(bytes), index))
+    }
+
+    fn deserialize_vec<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Vec<Self>, usize), DeserializeError> {
+        let (bytes, index) = read_bytes("Vec", data, index)?;
+        Ok((bytes.to_vec(), index))
+    }
+}
+
+impl DeserializerData for i8 {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (bytes, index) = read("i8", data.bytes(), index)?;
+        Ok((i8::from_le_bytes(bytes), index))
+    }
+
+    fn deserialize_vec<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Vec<Self>, usize), DeserializeError> {
+        deserialize_bulk("i8", data, index, i8::from_le_bytes)
+    }
+}
+
+/// Integers are read with the byte order of the config, or as a varint.
+macro_rules! deserializer_int {
+    ($($t:ident => $from_varint:expr),*) => {
+        $(
+            impl DeserializerData for $t {
+                fn try_from_data<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Self, usize), DeserializeError>
+                where
+                    Self: Sized,
+                {
+                    let config = data.config();
+                    if config.int_encoding() == IntEncoding::Varint {
+                        let (value, index) = read_varint(stringify!($t), data.bytes(), index, $t::BITS)?;
+                        return Ok(($from_varint(value) as $t, index));
+                    }
+                    let (bytes, index) = read(stringify!($t), data.bytes(), index)?;
+                    match config.byte_order() {
+                        ByteOrder::LittleEndian => Ok(($t::from_le_bytes(bytes), index)),
+                        ByteOrder::BigEndian => Ok(($t::from_be_bytes(bytes), index)),
+                    }
+                }
+
+                fn deserialize_vec<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Vec<Self>, usize), DeserializeError> {
+                    let config = data.config();
+                    match (config.int_encoding(), config.byte_order()) {
+                        (IntEncoding::Varint, _) => deserialize_elements(data, index),
+                        (IntEncoding::Fixed, ByteOrder::LittleEndian) => {
+                            deserialize_bulk(stringify!($t), data, index, $t::from_le_bytes)
+                        }
+                        (IntEncoding::Fixed, ByteOrder::BigEndian) => {
+                            deserialize_bulk(stringify!($t), data, index, $t::from_be_bytes)
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+deserializer_int!(
+    u16 => u128::from,
+    u32 => u128::from,
+    u64 => u128::from,
+    u128 => u128::from,
+    i16 => unzigzag,
+    i32 => unzigzag,
+    i64 => unzigzag,
+    i128 => unzigzag
+);
+
+/// Floats are read with all the bits in the byte order of the config, NaN payloads are kept.
+macro_rules! deserializer_float {
+    ($($t:ident),*) => {
+        $(
+            impl DeserializerData for $t {
+                fn try_from_data<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Self, usize), DeserializeError>
+                where
+                    Self: Sized,
+                {
+                    let (bytes, index) = read(stringify!($t), data.bytes(), index)?;
+                    match data.config().byte_order() {
+                        ByteOrder::LittleEndian => Ok(($t::from_le_bytes(bytes), index)),
+                        ByteOrder::BigEndian => Ok(($t::from_be_bytes(bytes), index)),
+                    }
+                }
+
+                fn deserialize_vec<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Vec<Self>, usize), DeserializeError> {
+                    match data.config().byte_order() {
+                        ByteOrder::LittleEndian => {
+                            deserialize_bulk(stringify!($t), data, index, $t::from_le_bytes)
+                        }
+                        ByteOrder::BigEndian => {
+                            deserialize_bulk(stringify!($t), data, index, $t::from_be_bytes)
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+deserializer_float!(f32, f64);
+
+/// An error is returned if the nanoseconds are not less than one second.
+impl DeserializerData for Duration {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (secs, index) = u64::try_from_data(data, index)?;
+        let (nanos, index) = read_nanos("Duration", data, index)?;
+        Ok((Duration::new(secs, nanos), index))
+    }
+}
+
+/// An error is returned if the nanoseconds are not less than one second,
+/// or if the time can not be represented on this platform.
+impl DeserializerData for SystemTime {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (secs, index) = i64::try_from_data(data, index)?;
+        let (nanos, index) = read_nanos("SystemTime", data, index)?;
+        let time = if secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::new(secs.unsigned_abs(), nanos))
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+                .and_then(|time| time.checked_add(Duration::from_nanos(u64::from(nanos))))
+        };
+        let time = time.ok_or(DeserializeError::OutOfRange {
+            type_name: "SystemTime",
+        })?;
+        Ok((time, index))
+    }
+}
+
+/// Read the nanoseconds of a time, less than one second.
+fn read_nanos<R: BitsRead + ?Sized>(
+    type_name: &'static str,
+    data: &R,
+    index: usize,
+) -> Result<(u32, usize), DeserializeError> {
+    let (nanos, index) = u32::try_from_data(data, index)?;
+    if nanos >= 1_000_000_000 {
+        return Err(DeserializeError::OutOfRange { type_name });
+    }
+    Ok((nanos, index))
+}
+
+/// Read as a u64, an error is returned if the value is too large for the architecture.
 impl DeserializerData for usize {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            usize::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-            ]),
-            index + 4,
-        )
+        let (value, index) = u64::try_from_data(data, index)?;
+        let value = usize::try_from(value)
+            .map_err(|_| DeserializeError::OutOfRange { type_name: "usize" })?;
+        Ok((value, index))
     }
 }
 
-impl DeserializerData for u8 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+/// Read as a i64, an error is returned if the value is too large for the architecture.
+impl DeserializerData for isize {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (u8::from_ne_bytes([data[index]]), index + 1)
+        let (value, index) = i64::try_from_data(data, index)?;
+        let value = isize::try_from(value)
+            .map_err(|_| DeserializeError::OutOfRange { type_name: "isize" })?;
+        Ok((value, index))
     }
 }
 
-impl DeserializerData for u16 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+/// Non-zero integers are read like the integer, a zero value is an error.
+macro_rules! deserializer_non_zero {
+    ($($t:ident => $int:ty),*) => {
+        $(
+            impl DeserializerData for $t {
+                fn try_from_data<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Self, usize), DeserializeError>
+                where
+                    Self: Sized,
+                {
+                    let (value, index) = <$int>::try_from_data(data, index)?;
+                    let value = $t::new(value).ok_or(DeserializeError::Zero {
+                        type_name: stringify!($t),
+                    })?;
+                    Ok((value, index))
+                }
+            }
+        )*
+    };
+}
+
+deserializer_non_zero!(
+    NonZeroU8 => u8,
+    NonZeroU16 => u16,
+    NonZeroU32 => u32,
+    NonZeroU64 => u64,
+    NonZeroU128 => u128,
+    NonZeroUsize => usize,
+    NonZeroI8 => i8,
+    NonZeroI16 => i16,
+    NonZeroI32 => i32,
+    NonZeroI64 => i64,
+    NonZeroI128 => i128,
+    NonZeroIsize => isize
+);
+
+/// Atomics are read like their value, into a new atomic.
+macro_rules! deserializer_atomic {
+    ($($t:ident => $value:ty, $size:literal),*) => {
+        $(
+            #[cfg(target_has_atomic = $size)]
+            impl DeserializerData for atomic::$t {
+                fn try_from_data<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Self, usize), DeserializeError>
+                where
+                    Self: Sized,
+                {
+                    let (value, index) = <$value>::try_from_data(data, index)?;
+                    Ok((atomic::$t::new(value), index))
+                }
+            }
+        )*
+    };
+}
+
+deserializer_atomic!(
+    AtomicBool => bool, "8",
+    AtomicU8 => u8, "8",
+    AtomicU16 => u16, "16",
+    AtomicU32 => u32, "32",
+    AtomicU64 => u64, "64",
+    AtomicUsize => usize, "ptr",
+    AtomicI8 => i8, "8",
+    AtomicI16 => i16, "16",
+    AtomicI32 => i32, "32",
+    AtomicI64 => i64, "64",
+    AtomicIsize => isize, "ptr"
+);
+
+impl DeserializerData for bool {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            u16::from_ne_bytes([data[index], data[index + 1]]),
-            index + 2,
-        )
+        let (value, index) = u8::try_from_data(data, index)?;
+        match value {
+            0 => Ok((false, index)),
+            1 => Ok((true, index)),
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "bool",
+                tag,
+            }),
+        }
     }
 }
 
-impl DeserializerData for u32 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+/// Read in UTF-8, the surrogates and the values out of the Unicode range are rejected.
+impl DeserializerData for char {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            u32::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-            ]),
-            index + 4,
-        )
+        let invalid = DeserializeError::InvalidUtf8 { type_name: "char" };
+        let ([first], _) = read("char", data.bytes(), index)?;
+        let size = match first {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Err(invalid),
+        };
+        let list = sub("char", data.bytes(), index, size)?;
+        match std::str::from_utf8(list)
+            .ok()
+            .and_then(|v| v.chars().next())
+        {
+            Some(value) => Ok((value, index + size)),
+            None => Err(invalid),
+        }
     }
 }
 
-impl DeserializerData for u64 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl DeserializerData for String {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            u64::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-                data[index + 4],
-                data[index + 5],
-                data[index + 6],
-                data[index + 7],
-            ]),
-            index + 8,
-        )
+        let (size, index) = usize::try_from_data(data, index)?;
+        let list = sub("String", data.bytes(), index, size)?;
+        let value = std::str::from_utf8(list).map_err(|_| DeserializeError::InvalidUtf8 {
+            type_name: "String",
+        })?;
+        Ok((value.to_owned(), index + size))
     }
 }
 
-impl DeserializerData for u128 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
-    where
-        Self: Sized,
-    {
-        (
-            u128::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-                data[index + 4],
-                data[index + 5],
-                data[index + 6],
-                data[index + 7],
-                data[index + 8],
-                data[index + 9],
-                data[index + 10],
-                data[index + 11],
-                data[index + 12],
-                data[index + 13],
-                data[index + 14],
-                data[index + 15],
-            ]),
-            index + 16,
-        )
+/// Read the size, then the bytes.
+fn read_bytes<'a, R: BitsRead + ?Sized>(
+    type_name: &'static str,
+    data: &'a R,
+    index: usize,
+) -> Result<(&'a [u8], usize), DeserializeError> {
+    let (size, index) = usize::try_from_data(data, index)?;
+    let bytes = sub(type_name, data.bytes(), index, size)?;
+    Ok((bytes, index + size))
+}
+
+/// Read as the raw bytes on Unix.
+///
+/// On the other platforms the bytes must be valid UTF-8, so a value written on Unix
+/// is only read if it is valid Unicode, an error is returned otherwise.
+impl DeserializerData for OsString {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (bytes, index) = read_bytes("OsString", data, index)?;
+        #[cfg(unix)]
+        let value = <OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes.to_vec());
+        #[cfg(not(unix))]
+        let value = std::str::from_utf8(bytes)
+            .map_err(|_| DeserializeError::InvalidUtf8 {
+                type_name: "OsString",
+            })?
+            .into();
+        Ok((value, index))
     }
 }
 
-#[cfg(target_pointer_width = "64")]
-impl DeserializerData for isize {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
-    where
-        Self: Sized,
-    {
-        (
-            isize::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-                data[index + 4],
-                data[index + 5],
-                data[index + 6],
-                data[index + 7],
-            ]),
-            index + 8,
-        )
+/// Read like an `OsString`.
+impl DeserializerData for PathBuf {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = OsString::try_from_data(data, index)?;
+        Ok((PathBuf::from(value), index))
     }
 }
 
-#[cfg(target_pointer_width = "32")]
-impl DeserializerData for isize {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+/// An error is returned if the bytes contain a nul.
+impl DeserializerData for CString {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            isize::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-            ]),
-            index + 4,
-        )
+        let (bytes, index) = read_bytes("CString", data, index)?;
+        let value = CString::new(bytes).map_err(|e| DeserializeError::InteriorNul {
+            type_name: "CString",
+            position: e.nul_position(),
+        })?;
+        Ok((value, index))
     }
 }
 
-impl DeserializerData for i8 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData> DeserializerData for Option<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (i8::from_ne_bytes([data[index]]), index + 1)
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => Ok((None, index)),
+            1 => {
+                let (value, index) = T::try_from_data(data, index)?;
+                Ok((Some(value), index))
+            }
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "Option",
+                tag,
+            }),
+        }
     }
 }
 
-impl DeserializerData for i16 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData, E: DeserializerData> DeserializerData for Result<T, E> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            i16::from_ne_bytes([data[index], data[index + 1]]),
-            index + 2,
-        )
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => {
+                let (value, index) = T::try_from_data(data, index)?;
+                Ok((Ok(value), index))
+            }
+            1 => {
+                let (error, index) = E::try_from_data(data, index)?;
+                Ok((Err(error), index))
+            }
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "Result",
+                tag,
+            }),
+        }
     }
 }
 
-impl DeserializerData for i32 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData> DeserializerData for Range<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            i32::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-            ]),
-            index + 4,
-        )
+        let (start, index) = T::try_from_data(data, index)?;
+        let (end, index) = T::try_from_data(data, index)?;
+        Ok((start..end, index))
     }
 }
 
-impl DeserializerData for i64 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData> DeserializerData for RangeInclusive<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        (
-            i64::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-                data[index + 4],
-                data[index + 5],
-                data[index + 6],
-                data[index + 7],
-            ]),
-            index + 8,
-        )
+        let (start, index) = T::try_from_data(data, index)?;
+        let (end, index) = T::try_from_data(data, index)?;
+        Ok((start..=end, index))
     }
 }
 
-impl DeserializerData for i128 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
-    where
-        Self: Sized,
-    {
-        (
-            i128::from_ne_bytes([
-                data[index],
-                data[index + 1],
-                data[index + 2],
-                data[index + 3],
-                data[index + 4],
-                data[index + 5],
-                data[index + 6],
-                data[index + 7],
-                data[index + 8],
-                data[index + 9],
-                data[index + 10],
-                data[index + 11],
-                data[index + 12],
-                data[index + 13],
-                data[index + 14],
-                data[index + 15],
-            ]),
-            index + 16,
-        )
+impl<T: DeserializerData> DeserializerData for RangeFrom<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (start, index) = T::try_from_data(data, index)?;
+        Ok((start.., index))
     }
 }
 
-impl DeserializerData for bool {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData> DeserializerData for RangeTo<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (value, index) = u8::from_data(data, index);
-        (value == 1, index)
+        let (end, index) = T::try_from_data(data, index)?;
+        Ok((..end, index))
     }
 }
 
-impl DeserializerData for char {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData> DeserializerData for RangeToInclusive<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (bit, index) = u8::from_data(data, index);
-        (bit as char, index)
+        let (end, index) = T::try_from_data(data, index)?;
+        Ok((..=end, index))
     }
 }
 
-impl DeserializerData for String {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl DeserializerData for RangeFull {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        _data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (size, index) = usize::from_data(data, index);
-        let list = sub("String", data, index, size);
-        (String::from_utf8(list).unwrap(), index + size)
+        Ok((.., index))
     }
 }
 
-impl<T: DeserializerData> DeserializerData for Option<T> {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl<T: DeserializerData> DeserializerData for Bound<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (code, index) = u8::from_data(data, index);
-        if code == 1 {
-            let (value, index) = T::from_data(data, index);
-            (Some(value), index)
-        } else {
-            (None, index)
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => {
+                let (value, index) = T::try_from_data(data, index)?;
+                Ok((Bound::Included(value), index))
+            }
+            1 => {
+                let (value, index) = T::try_from_data(data, index)?;
+                Ok((Bound::Excluded(value), index))
+            }
+            2 => Ok((Bound::Unbounded, index)),
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "Bound",
+                tag,
+            }),
         }
     }
 }
 
-impl<T: DeserializerData> DeserializerData for Vec<T> {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+impl DeserializerData for Ordering {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("Vec", data, index, size);
-        while !list.is_empty() {
-            let (e, e_index) = T::from_data(&list, 0);
-            res.push(e);
-            list = list[e_index..].to_vec();
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => Ok((Ordering::Less, index)),
+            1 => Ok((Ordering::Equal, index)),
+            2 => Ok((Ordering::Greater, index)),
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "Ordering",
+                tag,
+            }),
         }
-        (res, index + size)
+    }
+}
+
+impl DeserializerData for () {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        _data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        Ok(((), index))
+    }
+}
+
+impl<T: ?Sized> DeserializerData for PhantomData<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        _data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        Ok((PhantomData, index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Reverse<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((Reverse(value), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Wrapping<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((Wrapping(value), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Saturating<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((Saturating(value), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Vec<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        T::deserialize_vec(data, index)
     }
 }
 
 impl<T: DeserializerData> DeserializerData for VecDeque<T> {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("VecDeque", data, index, size);
-        while !list.is_empty() {
-            let (e, e_index) = T::from_data(&list, 0);
-            res.push_back(e);
-            list = list[e_index..].to_vec();
-        }
-        (res, index + size)
+        deserialize_sized(
+            "VecDeque",
+            data,
+            index,
+            Self::with_capacity,
+            Self::push_back,
+        )
     }
 }
 
 impl<T: DeserializerData> DeserializerData for LinkedList<T> {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("LinkedList", data, index, size);
-        while !list.is_empty() {
-            let (e, e_index) = T::from_data(&list, 0);
-            res.push_back(e);
-            list = list[e_index..].to_vec();
-        }
-        (res, index + size)
+        deserialize_sized("LinkedList", data, index, |_| Self::new(), Self::push_back)
     }
 }
 
-impl<T: DeserializerData> DeserializerData for HashSet<T>
+/// Preallocated with the default hasher of `S`.
+impl<T: DeserializerData, S> DeserializerData for HashSet<T, S>
 where
     T: Eq,
     T: PartialEq,
     T: Hash,
+    S: BuildHasher + Default,
 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("HashSet", data, index, size);
-        while !list.is_empty() {
-            let (e, e_index) = T::from_data(&list, 0);
-            res.insert(e);
-            list = list[e_index..].to_vec();
-        }
-        (res, index + size)
+        deserialize_sized(
+            "HashSet",
+            data,
+            index,
+            |capacity| Self::with_capacity_and_hasher(capacity, S::default()),
+            |res, e| {
+                res.insert(e);
+            },
+        )
     }
 }
 
@@ -417,19 +1337,22 @@ where
     T: Hash,
     T: Ord,
 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("BTreeSet", data, index, size);
-        while !list.is_empty() {
-            let (e, e_index) = T::from_data(&list, 0);
-            res.insert(e);
-            list = list[e_index..].to_vec();
-        }
-        (res, index + size)
+        deserialize_sized(
+            "BTreeSet",
+            data,
+            index,
+            |_| Self::new(),
+            |res, e| {
+                res.insert(e);
+            },
+        )
     }
 }
 
@@ -440,42 +1363,41 @@ where
     T: Hash,
     T: Ord,
 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("BinaryHeap", data, index, size);
-        while !list.is_empty() {
-            let (e, e_index) = T::from_data(&list, 0);
-            res.push(e);
-            list = list[e_index..].to_vec();
-        }
-        (res, index + size)
+        deserialize_sized("BinaryHeap", data, index, Self::with_capacity, Self::push)
     }
 }
 
-impl<K: DeserializerData, V: DeserializerData> DeserializerData for HashMap<K, V>
+/// Preallocated with the default hasher of `S`.
+impl<K: DeserializerData, V: DeserializerData, S> DeserializerData for HashMap<K, V, S>
 where
     K: Eq,
     K: PartialEq,
     K: Hash,
+    S: BuildHasher + Default,
 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("HashMap", data, index, size);
-        while !list.is_empty() {
-            let (key, e_index) = K::from_data(&list, 0);
-            let (value, e_index) = V::from_data(&list, e_index);
-            res.insert(key, value);
-            list = list[e_index..].to_vec();
-        }
-        (res, index + size)
+        deserialize_sized_map(
+            "HashMap",
+            data,
+            index,
+            |capacity| Self::with_capacity_and_hasher(capacity, S::default()),
+            |res, key, value| {
+                res.insert(key, value);
+            },
+        )
     }
 }
 
@@ -486,30 +1408,514 @@ where
     K: Hash,
     K: Ord,
 {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        deserialize_sized_map(
+            "BTreeMap",
+            data,
+            index,
+            |_| Self::new(),
+            |res, key, value| {
+                res.insert(key, value);
+            },
+        )
+    }
+}
+
+impl DeserializerData for Ipv4Addr {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (octets, index) = read::<4>("Ipv4Addr", data.bytes(), index)?;
+        Ok((Ipv4Addr::from(octets), index))
+    }
+}
+
+impl DeserializerData for Ipv6Addr {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let mut res = Self::new();
-        let (size, index) = usize::from_data(data, index);
-        let mut list = sub("BTreeMap", data, index, size);
-        while !list.is_empty() {
-            let (key, e_index) = K::from_data(&list, 0);
-            let (value, e_index) = V::from_data(&list, e_index);
-            res.insert(key, value);
-            list = list[e_index..].to_vec();
+        let (octets, index) = read::<16>("Ipv6Addr", data.bytes(), index)?;
+        Ok((Ipv6Addr::from(octets), index))
+    }
+}
+
+impl DeserializerData for IpAddr {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => {
+                let (ip, index) = Ipv4Addr::try_from_data(data, index)?;
+                Ok((IpAddr::V4(ip), index))
+            }
+            1 => {
+                let (ip, index) = Ipv6Addr::try_from_data(data, index)?;
+                Ok((IpAddr::V6(ip), index))
+            }
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "IpAddr",
+                tag,
+            }),
         }
-        (res, index + size)
+    }
+}
+
+impl DeserializerData for SocketAddrV4 {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (ip, index) = Ipv4Addr::try_from_data(data, index)?;
+        let (port, index) = u16::try_from_data(data, index)?;
+        Ok((SocketAddrV4::new(ip, port), index))
+    }
+}
+
+impl DeserializerData for SocketAddrV6 {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (ip, index) = Ipv6Addr::try_from_data(data, index)?;
+        let (port, index) = u16::try_from_data(data, index)?;
+        let (flowinfo, index) = u32::try_from_data(data, index)?;
+        let (scope_id, index) = u32::try_from_data(data, index)?;
+        Ok((SocketAddrV6::new(ip, port, flowinfo, scope_id), index))
     }
 }
 
 impl DeserializerData for SocketAddr {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => {
+                let (addr, index) = SocketAddrV4::try_from_data(data, index)?;
+                Ok((SocketAddr::V4(addr), index))
+            }
+            1 => {
+                let (addr, index) = SocketAddrV6::try_from_data(data, index)?;
+                Ok((SocketAddr::V6(addr), index))
+            }
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "SocketAddr",
+                tag,
+            }),
+        }
+    }
+}
+
+/// # LegacySocketAddr
+///
+/// `SocketAddr` written as text (size and UTF-8 bytes), the encoding of the previous versions.
+///
+/// Used to read (or write) the data of the previous versions, the `SocketAddr` is now written
+/// in binary.
+///
+/// Example :
+///
+/// ```
+/// use std::net::SocketAddr;
+/// use serialize_bits::{des::{DeserializerData, LegacySocketAddr}, ser::SerializerData};
+///
+/// let data = String::from("127.0.0.1:3000").to_data();
+/// let (LegacySocketAddr(addr), _) = LegacySocketAddr::try_from_data(&data, 0).unwrap();
+/// assert_eq!("127.0.0.1:3000".parse::<SocketAddr>().unwrap(), addr);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LegacySocketAddr(pub SocketAddr);
+
+impl DeserializerData for LegacySocketAddr {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = String::try_from_data(data, index)?;
+        match value.parse() {
+            Ok(addr) => Ok((LegacySocketAddr(addr), index)),
+            Err(_) => Err(DeserializeError::InvalidSocketAddr { value }),
+        }
+    }
+}
+
+/// Arrays are read without size, the size is known from the type.
+impl<T: DeserializerData, const N: usize> DeserializerData for [T; N] {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let mut list = Vec::with_capacity(N);
+        let mut index = index;
+        for _ in 0..N {
+            let (e, e_index) = T::try_from_data(data, index)?;
+            list.push(e);
+            index = e_index;
+        }
+        match list.try_into() {
+            Ok(res) => Ok((res, index)),
+            Err(_) => unreachable!("the list has N elements"),
+        }
+    }
+}
+
+/// Tuples are read element by element, without size.
+macro_rules! deserializer_tuple {
+    ($(($($t:ident $v:ident),+)),*) => {
+        $(
+            impl<$($t: DeserializerData),+> DeserializerData for ($($t,)+) {
+                fn try_from_data<R: BitsRead + ?Sized>(
+                    data: &R,
+                    index: usize,
+                ) -> Result<(Self, usize), DeserializeError>
+                where
+                    Self: Sized,
+                {
+                    $(let ($v, index) = $t::try_from_data(data, index)?;)+
+                    Ok((($($v,)+), index))
+                }
+            }
+        )*
+    };
+}
+
+deserializer_tuple!(
+    (A a),
+    (A a, B b),
+    (A a, B b, C c),
+    (A a, B b, C c, D d),
+    (A a, B b, C c, D d, E e),
+    (A a, B b, C c, D d, E e, F f),
+    (A a, B b, C c, D d, E e, F f, G g),
+    (A a, B b, C c, D d, E e, F f, G g, H h),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o, P p)
+);
+
+impl<T: DeserializerData> DeserializerData for Box<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((Box::new(value), index))
+    }
+}
+
+impl DeserializerData for Box<str> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = String::try_from_data(data, index)?;
+        Ok((value.into_boxed_str(), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Box<[T]> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = Vec::try_from_data(data, index)?;
+        Ok((value.into_boxed_slice(), index))
+    }
+}
+
+/// Shared again by a [`GraphReader`](crate::graph::GraphReader).
+impl<T: DeserializerData + 'static> DeserializerData for Rc<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_shared("Rc", data, index)
+    }
+}
+
+/// Shared again by a [`GraphReader`](crate::graph::GraphReader).
+impl DeserializerData for Rc<str> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_shared_with("Rc", data, index, |data, index| {
+            let (value, index) = String::try_from_data(data, index)?;
+            Ok((Rc::from(value), index))
+        })
+    }
+}
+
+/// Shared again by a [`GraphReader`](crate::graph::GraphReader).
+impl<T: DeserializerData + 'static> DeserializerData for Rc<[T]> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_shared_with("Rc", data, index, |data, index| {
+            let (value, index) = Vec::try_from_data(data, index)?;
+            Ok((Rc::from(value), index))
+        })
+    }
+}
+
+/// Shared again by a [`GraphReader`](crate::graph::GraphReader).
+impl<T: DeserializerData + 'static> DeserializerData for Arc<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_shared("Arc", data, index)
+    }
+}
+
+/// Shared again by a [`GraphReader`](crate::graph::GraphReader).
+impl DeserializerData for Arc<str> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_shared_with("Arc", data, index, |data, index| {
+            let (value, index) = String::try_from_data(data, index)?;
+            Ok((Arc::from(value), index))
+        })
+    }
+}
+
+/// Shared again by a [`GraphReader`](crate::graph::GraphReader).
+impl<T: DeserializerData + 'static> DeserializerData for Arc<[T]> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_shared_with("Arc", data, index, |data, index| {
+            let (value, index) = Vec::try_from_data(data, index)?;
+            Ok((Arc::from(value), index))
+        })
+    }
+}
+
+/// Returns an error if the data is not read by a [`GraphReader`](crate::graph::GraphReader).
+impl<T: DeserializerData + 'static> DeserializerData for rc::Weak<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_weak::<Rc<T>, R>("Weak", data, index)
+    }
+}
+
+/// Returns an error if the data is not read by a [`GraphReader`](crate::graph::GraphReader).
+impl<T: DeserializerData + 'static> DeserializerData for sync::Weak<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        graph::deserialize_weak::<Arc<T>, R>("Weak", data, index)
+    }
+}
+
+/// Always read as `Cow::Owned`.
+impl<T: ToOwned + ?Sized> DeserializerData for Cow<'_, T>
+where
+    T::Owned: DeserializerData,
+{
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::Owned::try_from_data(data, index)?;
+        Ok((Cow::Owned(value), index))
+    }
+}
+
+impl<'de> BorrowDeserializerData<'de> for &'de [u8] {
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError> {
+        read_bytes("&[u8]", data, index)
+    }
+}
+
+impl<'de> BorrowDeserializerData<'de> for &'de str {
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError> {
+        let (bytes, index) = read_bytes("&str", data, index)?;
+        let value = std::str::from_utf8(bytes)
+            .map_err(|_| DeserializeError::InvalidUtf8 { type_name: "&str" })?;
+        Ok((value, index))
+    }
+}
+
+/// Always read as `Cow::Borrowed`.
+impl<'de> BorrowDeserializerData<'de> for Cow<'de, str> {
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError> {
+        let (value, index) = <&str>::try_borrow_from_data(data, index)?;
+        Ok((Cow::Borrowed(value), index))
+    }
+}
+
+/// Always read as `Cow::Borrowed`.
+impl<'de> BorrowDeserializerData<'de> for Cow<'de, [u8]> {
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError> {
+        let (value, index) = <&[u8]>::try_borrow_from_data(data, index)?;
+        Ok((Cow::Borrowed(value), index))
+    }
+}
+
+impl<'de, T: BorrowDeserializerData<'de>> BorrowDeserializerData<'de> for Option<T> {
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError> {
+        let (code, index) = u8::try_from_data(data, index)?;
+        match code {
+            0 => Ok((None, index)),
+            1 => {
+                let (value, index) = T::try_borrow_from_data(data, index)?;
+                Ok((Some(value), index))
+            }
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "Option",
+                tag,
+            }),
+        }
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Cell<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((Cell::new(value), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for RefCell<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((RefCell::new(value), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for Mutex<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((Mutex::new(value), index))
+    }
+}
+
+impl<T: DeserializerData> DeserializerData for RwLock<T> {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (value, index) = String::from_data(data, index);
-        let addr = value.parse().unwrap();
-        (addr, index)
+        let (value, index) = T::try_from_data(data, index)?;
+        Ok((RwLock::new(value), index))
     }
 }
@@ -0,0 +1,532 @@
+use std::{
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
+    collections::{hash_map::Entry, HashMap},
+    fmt, io,
+    ops::Deref,
+    panic::{self, AssertUnwindSafe},
+    rc::{self, Rc},
+    sync::{self, Arc},
+};
+
+use crate::{
+    config::Config,
+    des::{BitsRead, DeserializeError, DeserializerData},
+    ser::{BitsWrite, SerializerData},
+};
+
+/// Tag of a `Weak` without value.
+const NULL: u8 = 0;
+/// Tag of an object written for the first time, followed by its value.
+const NEW: u8 = 1;
+/// Tag of an object already written, followed by its id.
+const REF: u8 = 2;
+
+/// # GraphWriter
+///
+/// Destination writing each `Rc` and `Arc` object once, to keep the sharing and the cycles.
+///
+/// The first time an object is written, it gets the next id (0, 1, 2, ...) and its value is
+/// written, then only its id is written. `Weak` can only be written by a `GraphWriter`.
+///
+/// The data must be read with a [`GraphReader`] using the same config.
+///
+/// Example :
+///
+/// ```
+/// use std::rc::Rc;
+/// use serialize_bits::{config::Config, graph::{GraphReader, GraphWriter}};
+/// use serialize_bits::{des::DeserializerData, ser::SerializerData};
+///
+/// let city = Rc::new(String::from("Paris"));
+/// let mut out = GraphWriter::new(Vec::new(), Config::default());
+/// vec![city.clone(), city].serialize_into(&mut out).unwrap();
+/// let data = out.into_inner();
+///
+/// let reader = GraphReader::new(&data, Config::default());
+/// let (cities, _) = Vec::<Rc<String>>::try_from_data(&reader, 0).unwrap();
+/// assert!(Rc::ptr_eq(&cities[0], &cities[1]));
+/// ```
+#[derive(Debug)]
+pub struct GraphWriter<W> {
+    inner: W,
+    config: Config,
+    graph: WriteGraph,
+}
+
+impl<W: BitsWrite> GraphWriter<W> {
+    /// Write into the destination with the config.
+    pub fn new(inner: W, config: Config) -> Self {
+        Self {
+            inner,
+            config,
+            graph: WriteGraph::default(),
+        }
+    }
+
+    /// Return the destination.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: BitsWrite> BitsWrite for GraphWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.inner.write_bytes(bytes)
+    }
+
+    fn config(&self) -> Config {
+        self.config
+    }
+
+    fn graph(&mut self) -> Option<&mut WriteGraph> {
+        Some(&mut self.graph)
+    }
+}
+
+/// # WriteGraph
+///
+/// Ids of the objects already written by a [`GraphWriter`].
+///
+/// The objects are identified by their address, so a clone of each `Rc` and `Arc` is kept
+/// until the writer is dropped, the address of an object written can not be reused by another.
+#[derive(Debug, Default)]
+pub struct WriteGraph {
+    ids: HashMap<*const (), usize>,
+    objects: Vec<Box<dyn Any>>,
+}
+
+impl WriteGraph {
+    /// Id of the object if it was already written, else keep it and register it with the next id.
+    fn id<P>(&mut self, pointer: &P) -> Option<usize>
+    where
+        P: Deref + Clone + 'static,
+    {
+        let ptr = &**pointer as *const P::Target as *const ();
+        let next = self.ids.len();
+        match self.ids.entry(ptr) {
+            Entry::Occupied(e) => Some(*e.get()),
+            Entry::Vacant(e) => {
+                e.insert(next);
+                self.objects.push(Box::new(pointer.clone()));
+                None
+            }
+        }
+    }
+}
+
+/// Write the value of the object the first time, then its id.
+pub(crate) fn serialize_shared<P, W>(pointer: &P, out: &mut W) -> io::Result<()>
+where
+    P: Deref + Clone + 'static,
+    P::Target: SerializerData,
+    W: BitsWrite + ?Sized,
+{
+    match out.graph().map(|graph| graph.id(pointer)) {
+        None => pointer.deref().serialize_into(out),
+        Some(None) => {
+            NEW.serialize_into(out)?;
+            pointer.deref().serialize_into(out)
+        }
+        Some(Some(id)) => {
+            REF.serialize_into(out)?;
+            id.serialize_into(out)
+        }
+    }
+}
+
+/// Write a `Weak` with the value of the object (`None` if the object was dropped).
+pub(crate) fn serialize_weak<P, W>(value: Option<&P>, out: &mut W) -> io::Result<()>
+where
+    P: Deref + Clone + 'static,
+    P::Target: SerializerData,
+    W: BitsWrite + ?Sized,
+{
+    if out.graph().is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "a Weak can only be written by a GraphWriter",
+        ));
+    }
+    match value {
+        None => NULL.serialize_into(out),
+        Some(value) => serialize_shared(value, out),
+    }
+}
+
+/// # GraphReader
+///
+/// Source of data written by a [`GraphWriter`], the objects written once are shared again.
+///
+/// The reader keeps every object read, so an object only referenced by `Weak` stays alive
+/// until the reader is dropped.
+#[derive(Debug)]
+pub struct GraphReader<'a> {
+    data: &'a [u8],
+    config: Config,
+    graph: ReadGraph,
+}
+
+impl<'a> GraphReader<'a> {
+    /// Read the data with the config.
+    pub fn new(data: &'a [u8], config: Config) -> Self {
+        Self {
+            data,
+            config,
+            graph: ReadGraph::default(),
+        }
+    }
+}
+
+impl BitsRead for GraphReader<'_> {
+    fn bytes(&self) -> &[u8] {
+        self.data
+    }
+
+    fn config(&self) -> Config {
+        self.config
+    }
+
+    fn graph(&self) -> Option<&ReadGraph> {
+        Some(&self.graph)
+    }
+}
+
+/// Object read by a [`GraphReader`], in the order of the ids.
+enum Object {
+    /// The value of the object is being read, with the type of its `Weak` (none for `str` and `[T]`)
+    /// and its `Weak` once it is allocated.
+    InProgress {
+        weak_type: Option<TypeId>,
+        weak: Option<Box<dyn Any>>,
+    },
+    /// The `Rc` or `Arc` of the object.
+    Done(Box<dyn Any>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Mode {
+    /// No object is being read.
+    #[default]
+    Normal,
+    /// An object is read for the first time, a `Weak` to an object in progress is not linked.
+    Attempt,
+    /// The object was read without error, it is read again with the `Weak` linked.
+    Linked,
+}
+
+/// # ReadGraph
+///
+/// Objects already read by a [`GraphReader`].
+///
+/// An object containing a `Weak` to itself (or to an object containing it) can only be
+/// allocated once its value is read, so it is read a first time to find the errors and the
+/// cycles, then a second time with `Rc::new_cyclic` if a cycle was found.
+/// If the second reading fails (a `DeserializerData` giving another result), its error is returned,
+/// the allocation is abandoned by unwinding so the crate must not be built with `panic = "abort"`.
+#[derive(Default)]
+pub struct ReadGraph {
+    objects: RefCell<Vec<Object>>,
+    mode: Cell<Mode>,
+    cycle: Cell<bool>,
+}
+
+impl fmt::Debug for ReadGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadGraph")
+            .field("objects", &self.objects.borrow().len())
+            .finish()
+    }
+}
+
+impl ReadGraph {
+    fn push(&self, weak_type: Option<TypeId>) -> usize {
+        let mut objects = self.objects.borrow_mut();
+        objects.push(Object::InProgress {
+            weak_type,
+            weak: None,
+        });
+        objects.len() - 1
+    }
+
+    fn set(&self, id: usize, object: Object) {
+        self.objects.borrow_mut()[id] = object;
+    }
+
+    fn truncate(&self, id: usize) {
+        self.objects.borrow_mut().truncate(id);
+    }
+
+    fn done<P: Clone + 'static>(&self, id: usize, pointer: P) -> P {
+        self.set(id, Object::Done(Box::new(pointer.clone())));
+        pointer
+    }
+
+    /// The pointer of an object already read.
+    fn get<P: Clone + 'static>(
+        &self,
+        type_name: &'static str,
+        id: usize,
+    ) -> Result<P, DeserializeError> {
+        match self.objects.borrow().get(id) {
+            Some(Object::Done(pointer)) => pointer
+                .downcast_ref::<P>()
+                .cloned()
+                .ok_or(DeserializeError::InvalidReference { type_name, id }),
+            _ => Err(DeserializeError::InvalidReference { type_name, id }),
+        }
+    }
+
+    /// The `Weak` of an object already read or in progress.
+    ///
+    /// The type of the `Weak` is checked even before the object is allocated,
+    /// so the first reading and the linked one always give the same result.
+    fn get_weak<P: Pointer>(
+        &self,
+        type_name: &'static str,
+        id: usize,
+    ) -> Result<P::Weak, DeserializeError> {
+        let invalid = DeserializeError::InvalidReference { type_name, id };
+        match self.objects.borrow().get(id) {
+            Some(Object::Done(pointer)) => {
+                pointer.downcast_ref::<P>().map(P::downgrade).ok_or(invalid)
+            }
+            Some(Object::InProgress {
+                weak: Some(weak), ..
+            }) => weak.downcast_ref::<P::Weak>().cloned().ok_or(invalid),
+            Some(Object::InProgress {
+                weak_type,
+                weak: None,
+            }) if *weak_type == Some(TypeId::of::<P::Weak>()) => {
+                self.cycle.set(true);
+                Ok(P::Weak::default())
+            }
+            _ => Err(invalid),
+        }
+    }
+
+    /// Read the value of a new object built without `Weak` to itself,
+    /// `weak_type` is the type of the `Weak` that can refer to it while it is read.
+    fn read_new_with<P, R>(
+        &self,
+        data: &R,
+        index: usize,
+        weak_type: Option<TypeId>,
+        read: impl FnOnce(&R, usize) -> Result<(P, usize), DeserializeError>,
+    ) -> Result<(P, usize), DeserializeError>
+    where
+        P: Clone + 'static,
+        R: BitsRead + ?Sized,
+    {
+        let id = self.push(weak_type);
+        let (pointer, index) = read(data, index)?;
+        Ok((self.done(id, pointer), index))
+    }
+
+    /// Read the value of a new object, linking the `Weak` to the object in progress.
+    fn read_new<P, R>(&self, data: &R, index: usize) -> Result<(P, usize), DeserializeError>
+    where
+        P: Pointer,
+        R: BitsRead + ?Sized,
+    {
+        match self.mode.get() {
+            Mode::Attempt => {
+                let weak_type = Some(TypeId::of::<P::Weak>());
+                self.read_new_with(data, index, weak_type, |data, index| {
+                    let (value, index) = P::Target::try_from_data(data, index)?;
+                    Ok((P::new(value), index))
+                })
+            }
+            Mode::Linked => {
+                let weak_type = Some(TypeId::of::<P::Weak>());
+                let id = self.push(weak_type);
+                let mut next = Ok(index);
+                let pointer = panic::catch_unwind(AssertUnwindSafe(|| {
+                    P::new_cyclic(|weak| {
+                        let weak = Some(Box::new(weak.clone()) as Box<dyn Any>);
+                        self.set(id, Object::InProgress { weak_type, weak });
+                        match P::Target::try_from_data(data, index) {
+                            Ok((value, index)) => {
+                                next = Ok(index);
+                                value
+                            }
+                            // The value read the first time is not read again (the `DeserializerData`
+                            // is not deterministic), the allocation is abandoned without value,
+                            // `resume_unwind` does not call the panic hook.
+                            Err(e) => {
+                                next = Err(e);
+                                panic::resume_unwind(Box::new(Abandoned))
+                            }
+                        }
+                    })
+                }));
+                match (pointer, next) {
+                    (_, Err(e)) => Err(e),
+                    (Ok(pointer), Ok(next)) => Ok((self.done(id, pointer), next)),
+                    (Err(payload), Ok(_)) => panic::resume_unwind(payload),
+                }
+            }
+            Mode::Normal => {
+                let id = self.objects.borrow().len();
+                self.mode.set(Mode::Attempt);
+                self.cycle.set(false);
+                let res = self.read_new::<P, R>(data, index);
+                self.mode.set(Mode::Normal);
+                match res {
+                    Ok(res) if !self.cycle.get() => return Ok(res),
+                    Ok(_) => self.truncate(id),
+                    Err(e) => {
+                        self.truncate(id);
+                        return Err(e);
+                    }
+                }
+                self.mode.set(Mode::Linked);
+                let res = self.read_new::<P, R>(data, index);
+                self.mode.set(Mode::Normal);
+                if res.is_err() {
+                    self.truncate(id);
+                }
+                res
+            }
+        }
+    }
+}
+
+/// Payload unwinding out of `new_cyclic` when the value can not be read the second time.
+struct Abandoned;
+
+/// `Rc` or `Arc` of a sized value.
+pub(crate) trait Pointer: Clone + 'static {
+    type Target: DeserializerData;
+    type Weak: Clone + Default + 'static;
+
+    fn new(value: Self::Target) -> Self;
+    fn new_cyclic(value: impl FnOnce(&Self::Weak) -> Self::Target) -> Self;
+    fn downgrade(&self) -> Self::Weak;
+}
+
+impl<T: DeserializerData + 'static> Pointer for Rc<T> {
+    type Target = T;
+    type Weak = rc::Weak<T>;
+
+    fn new(value: T) -> Self {
+        Rc::new(value)
+    }
+
+    fn new_cyclic(value: impl FnOnce(&rc::Weak<T>) -> T) -> Self {
+        Rc::new_cyclic(value)
+    }
+
+    fn downgrade(&self) -> rc::Weak<T> {
+        Rc::downgrade(self)
+    }
+}
+
+impl<T: DeserializerData + 'static> Pointer for Arc<T> {
+    type Target = T;
+    type Weak = sync::Weak<T>;
+
+    fn new(value: T) -> Self {
+        Arc::new(value)
+    }
+
+    fn new_cyclic(value: impl FnOnce(&sync::Weak<T>) -> T) -> Self {
+        Arc::new_cyclic(value)
+    }
+
+    fn downgrade(&self) -> sync::Weak<T> {
+        Arc::downgrade(self)
+    }
+}
+
+/// Read the tag of a shared object, then its value or its id.
+fn read_shared<P, R>(
+    type_name: &'static str,
+    data: &R,
+    index: usize,
+    graph: &ReadGraph,
+    new: impl FnOnce(&R, usize) -> Result<(P, usize), DeserializeError>,
+) -> Result<(P, usize), DeserializeError>
+where
+    P: Clone + 'static,
+    R: BitsRead + ?Sized,
+{
+    let (tag, index) = u8::try_from_data(data, index)?;
+    match tag {
+        NEW => new(data, index),
+        REF => {
+            let (id, index) = usize::try_from_data(data, index)?;
+            Ok((graph.get(type_name, id)?, index))
+        }
+        tag => Err(DeserializeError::InvalidTag { type_name, tag }),
+    }
+}
+
+/// Read an `Rc` or an `Arc` of a sized value, shared if the data was written by a `GraphWriter`.
+pub(crate) fn deserialize_shared<P, R>(
+    type_name: &'static str,
+    data: &R,
+    index: usize,
+) -> Result<(P, usize), DeserializeError>
+where
+    P: Pointer,
+    R: BitsRead + ?Sized,
+{
+    match data.graph() {
+        None => {
+            let (value, index) = P::Target::try_from_data(data, index)?;
+            Ok((P::new(value), index))
+        }
+        Some(graph) => read_shared(type_name, data, index, graph, |data, index| {
+            graph.read_new(data, index)
+        }),
+    }
+}
+
+/// Read an `Rc` or an `Arc` built from the value read by `read` (`str` or `[T]`),
+/// shared if the data was written by a `GraphWriter`.
+pub(crate) fn deserialize_shared_with<P, R>(
+    type_name: &'static str,
+    data: &R,
+    index: usize,
+    read: impl FnOnce(&R, usize) -> Result<(P, usize), DeserializeError>,
+) -> Result<(P, usize), DeserializeError>
+where
+    P: Clone + 'static,
+    R: BitsRead + ?Sized,
+{
+    match data.graph() {
+        None => read(data, index),
+        Some(graph) => read_shared(type_name, data, index, graph, |data, index| {
+            graph.read_new_with(data, index, None, read)
+        }),
+    }
+}
+
+/// Read a `Weak` written by a `GraphWriter`.
+pub(crate) fn deserialize_weak<P, R>(
+    type_name: &'static str,
+    data: &R,
+    index: usize,
+) -> Result<(P::Weak, usize), DeserializeError>
+where
+    P: Pointer,
+    R: BitsRead + ?Sized,
+{
+    let Some(graph) = data.graph() else {
+        return Err(DeserializeError::GraphRequired { type_name });
+    };
+    let (tag, index) = u8::try_from_data(data, index)?;
+    match tag {
+        NULL => Ok((P::Weak::default(), index)),
+        NEW => {
+            let (pointer, index) = graph.read_new::<P, R>(data, index)?;
+            Ok((pointer.downgrade(), index))
+        }
+        REF => {
+            let (id, index) = usize::try_from_data(data, index)?;
+            Ok((graph.get_weak::<P>(type_name, id)?, index))
+        }
+        tag => Err(DeserializeError::InvalidTag { type_name, tag }),
+    }
+}
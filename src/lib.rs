@@ -1,2 +1,5 @@
+pub mod bits;
+pub mod config;
 pub mod des;
-pub mod ser;
\ No newline at end of file
+pub mod graph;
+pub mod ser;
@@ -1,237 +1,1089 @@
 use std::{
+    borrow::{Cow, ToOwned},
+    cell::{Cell, RefCell},
+    cmp::{Ordering, Reverse},
     collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
-    net::SocketAddr,
+    ffi::{CStr, CString, OsStr, OsString},
+    io::{self, Write},
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num::{
+        NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping,
+    },
+    ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+    path::{Path, PathBuf},
+    rc::{self, Rc},
+    sync::{self, atomic, Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use crate::{
+    config::{ByteOrder, Config, IntEncoding, LengthPrefix},
+    des::{LegacySocketAddr, MAX_EMPTY_ELEMENTS},
+    graph::{self, WriteGraph},
+};
+
+/// Derive macro for [`SerializerData`], writing the fields in declaration order.
+#[cfg(feature = "derive")]
+pub use serialize_bits_derive::SerializerData;
+
+/// # BitsWrite
+///
+/// Destination of the serialized data.
+///
+/// Implemented for every [`std::io::Write`], so a `Vec<u8>`, a file or a socket can be used.
+pub trait BitsWrite {
+    /// Write all the bytes at the end of the destination.
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Options of the encoding, the default config if not overridden.
+    fn config(&self) -> Config {
+        Config::default()
+    }
+
+    /// Objects already written, only a [`GraphWriter`](crate::graph::GraphWriter) keeps them.
+    fn graph(&mut self) -> Option<&mut WriteGraph> {
+        None
+    }
+}
+
+impl<W: Write + ?Sized> BitsWrite for W {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_all(bytes)
+    }
+}
+
+/// # ConfigWriter
+///
+/// Destination using a specific [`Config`] for the encoding.
+#[derive(Debug)]
+pub struct ConfigWriter<W> {
+    inner: W,
+    config: Config,
+}
+
+impl<W: BitsWrite> ConfigWriter<W> {
+    /// Write into the destination with the config.
+    pub fn new(inner: W, config: Config) -> Self {
+        Self { inner, config }
+    }
+
+    /// Return the destination.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: BitsWrite> BitsWrite for ConfigWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.inner.write_bytes(bytes)
+    }
+
+    fn config(&self) -> Config {
+        self.config
+    }
+
+    fn graph(&mut self) -> Option<&mut WriteGraph> {
+        self.inner.graph()
+    }
+}
+
+/// Buffer with the config and the graph of the destination.
+///
+/// Not generic over the destination, so recursive types use the same buffer at every level.
+struct Buffer<'a> {
+    data: Vec<u8>,
+    config: Config,
+    graph: Option<&'a mut WriteGraph>,
+}
+
+impl<'a> Buffer<'a> {
+    fn new<W: BitsWrite + ?Sized>(out: &'a mut W) -> Self {
+        Self {
+            data: Vec::new(),
+            config: out.config(),
+            graph: out.graph(),
+        }
+    }
+}
+
+impl BitsWrite for Buffer<'_> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.data.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn config(&self) -> Config {
+        self.config
+    }
+
+    fn graph(&mut self) -> Option<&mut WriteGraph> {
+        self.graph.as_deref_mut()
+    }
+}
+
 /// # SerializerData
 ///
 /// Trait for convert Struct to data.
-/// 
+///
 /// The library already implements the trait for :
 /// - u8, u16, u32, u64, u128, usize
 /// - i8, i16, i32, i64, i128, isize
+/// - NonZeroU8, ..., NonZeroU128, NonZeroUsize, NonZeroI8, ..., NonZeroI128, NonZeroIsize
+/// - f32, f64
+/// - AtomicBool, AtomicU8, ..., AtomicU64, AtomicUsize, AtomicI8, ..., AtomicI64, AtomicIsize
+/// - Duration, SystemTime
 /// - char
 /// - bool
 /// - String
-/// - Option<T>
-/// - SocketAddr
+/// - OsStr, OsString, Path, PathBuf, CStr, CString
+/// - Option<T>, Result<T, E>
+/// - (), PhantomData<T>
+/// - Range<T>, RangeInclusive<T>, RangeFrom<T>, RangeTo<T>, RangeToInclusive<T>, RangeFull
+/// - Bound<T>
+/// - Ordering, Reverse<T>, Wrapping<T>, Saturating<T>
+/// - IpAddr, Ipv4Addr, Ipv6Addr
+/// - SocketAddr, SocketAddrV4, SocketAddrV6, LegacySocketAddr
+/// - (T1, ..., T16) tuples
+/// - [T; N] arrays
+/// - str, [T]
+/// - Box<T>, Rc<T>, Arc<T>, Cow<T>
+/// - rc::Weak<T>, sync::Weak<T> (with a GraphWriter)
+/// - Cell<T>, RefCell<T>, Mutex<T>, RwLock<T>
 /// - Vec<T>, VecDeque<T>, LinkedList<T>
-/// - HashSet<T>, BTreeSet<T>
+/// - HashSet<T, S>, BTreeSet<T>
 /// - BinaryHeap<T>
-/// - HashMap<K, V>, BTreeMap<K, V>
+/// - HashMap<K, V, S>, BTreeMap<K, V>
+/// - Sorted<HashSet<T, S>>, Sorted<HashMap<K, V, S>>
 pub trait SerializerData {
+    /// Write the Struct as bits into the destination.
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()>;
+
     /// Convert the Struct into bits (Vec<u8>).
-    fn to_data(&self) -> Vec<u8>;
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can not be written, see [`SerializerData::try_to_data`].
+    fn to_data(&self) -> Vec<u8> {
+        match self.try_to_data() {
+            Ok(res) => res,
+            Err(e) => panic!("Error when serialize: {e}"),
+        }
+    }
+
+    /// Convert the Struct into bits (Vec<u8>), or returns the error of the value.
+    ///
+    /// Writing into a `Vec<u8>` never fails, but some values can not be written:
+    /// a `RefCell` already mutably borrowed, a `Weak` without a `GraphWriter`,
+    /// a collection of elements written without data, a `SystemTime` out of range, ...
+    fn try_to_data(&self) -> io::Result<Vec<u8>> {
+        let mut res = Vec::new();
+        self.serialize_into(&mut res)?;
+        Ok(res)
+    }
+
+    /// Convert the Struct into bits (Vec<u8>) with the config.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can not be written, see [`SerializerData::try_to_data_with`].
+    fn to_data_with(&self, config: Config) -> Vec<u8> {
+        match self.try_to_data_with(config) {
+            Ok(res) => res,
+            Err(e) => panic!("Error when serialize: {e}"),
+        }
+    }
+
+    /// Convert the Struct into bits (Vec<u8>) with the config, or returns the error of the value.
+    fn try_to_data_with(&self, config: Config) -> io::Result<Vec<u8>> {
+        let mut out = ConfigWriter::new(Vec::new(), config);
+        self.serialize_into(&mut out)?;
+        Ok(out.into_inner())
+    }
+
+    /// Write a slice of Struct, the size of the elements followed by each element.
+    ///
+    /// The primitives write all the elements at once, the other types do not override it.
+    fn serialize_slice<W: BitsWrite + ?Sized>(slice: &[Self], out: &mut W) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        serialize_sized(slice, out)
+    }
 }
 
-impl SerializerData for usize {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Returns an error if the element was written without data, it could not be counted by the size.
+fn check_written(len: usize, buffer: &Buffer) -> io::Result<()> {
+    if buffer.data.len() == len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "an element of a collection can not be written without data",
+        ));
     }
+    Ok(())
 }
 
-impl SerializerData for u8 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Serialize the elements in a buffer, then write the size of the buffer followed by the buffer.
+///
+/// With [`LengthPrefix::Count`], the number of elements is written followed by each element.
+/// Above [`MAX_EMPTY_ELEMENTS`] elements, they are buffered to check that none is written without data,
+/// the reader would reject them.
+fn serialize_sized<'a, T, I, W>(elements: I, out: &mut W) -> io::Result<()>
+where
+    T: SerializerData + 'a,
+    I: IntoIterator<Item = &'a T>,
+    I::IntoIter: ExactSizeIterator,
+    W: BitsWrite + ?Sized,
+{
+    let elements = elements.into_iter();
+    let count = elements.len();
+    let by_count = out.config().length_prefix() == LengthPrefix::Count;
+    if by_count && count <= MAX_EMPTY_ELEMENTS {
+        count.serialize_into(out)?;
+        for e in elements {
+            e.serialize_into(out)?;
+        }
+        return Ok(());
+    }
+    let mut buffer = Buffer::new(out);
+    for e in elements {
+        let len = buffer.data.len();
+        e.serialize_into(&mut buffer)?;
+        check_written(len, &buffer)?;
     }
+    let data = buffer.data;
+    if by_count { count } else { data.len() }.serialize_into(out)?;
+    out.write_bytes(&data)
 }
 
-impl SerializerData for u16 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Serialize the entries in a buffer, then write the size of the buffer followed by the buffer.
+///
+/// With [`LengthPrefix::Count`], the number of entries is written followed by each key and value.
+/// Above [`MAX_EMPTY_ELEMENTS`] entries, they are buffered to check that none is written without data,
+/// the reader would reject them.
+fn serialize_sized_map<'a, K, V, I, W>(entries: I, out: &mut W) -> io::Result<()>
+where
+    K: SerializerData + 'a,
+    V: SerializerData + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+    I::IntoIter: ExactSizeIterator,
+    W: BitsWrite + ?Sized,
+{
+    let entries = entries.into_iter();
+    let count = entries.len();
+    let by_count = out.config().length_prefix() == LengthPrefix::Count;
+    if by_count && count <= MAX_EMPTY_ELEMENTS {
+        count.serialize_into(out)?;
+        for (key, value) in entries {
+            key.serialize_into(out)?;
+            value.serialize_into(out)?;
+        }
+        return Ok(());
+    }
+    let mut buffer = Buffer::new(out);
+    for (key, value) in entries {
+        let len = buffer.data.len();
+        key.serialize_into(&mut buffer)?;
+        value.serialize_into(&mut buffer)?;
+        check_written(len, &buffer)?;
     }
+    let data = buffer.data;
+    if by_count { count } else { data.len() }.serialize_into(out)?;
+    out.write_bytes(&data)
 }
 
-impl SerializerData for u32 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Sort the elements by the bytes of their key, encoded alone with the config.
+///
+/// The keys are encoded without the graph, only the order is taken from them: with a
+/// [`GraphWriter`](crate::graph::GraphWriter), the order is not the one of the bytes written.
+fn sort_by_encoded_key<'a, T, E>(
+    elements: impl Iterator<Item = E>,
+    key: impl Fn(&E) -> &'a T,
+    config: Config,
+) -> io::Result<Vec<E>>
+where
+    T: SerializerData + 'a,
+{
+    let mut keyed = Vec::new();
+    for e in elements {
+        let mut out = ConfigWriter::new(Vec::new(), config);
+        key(&e).serialize_into(&mut out)?;
+        keyed.push((out.into_inner(), e));
     }
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(keyed.into_iter().map(|(_, e)| e).collect())
 }
 
-impl SerializerData for u64 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Convert all the elements in a buffer, then write the size of the buffer followed by the buffer.
+///
+/// With [`LengthPrefix::Count`], the number of elements is written instead of the size.
+fn serialize_bulk<T: Copy, const N: usize, W: BitsWrite + ?Sized>(
+    slice: &[T],
+    out: &mut W,
+    to_bytes: impl Fn(T) -> [u8; N],
+) -> io::Result<()> {
+    let mut data = Vec::with_capacity(slice.len() * N);
+    for e in slice {
+        data.extend_from_slice(&to_bytes(*e));
+    }
+    match out.config().length_prefix() {
+        LengthPrefix::Bytes => data.len().serialize_into(out)?,
+        LengthPrefix::Count => slice.len().serialize_into(out)?,
     }
+    out.write_bytes(&data)
 }
 
-impl SerializerData for u128 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Write the value as a LEB128 varint, 7 bits per byte with the high bit set on all but the last byte.
+fn serialize_varint<W: BitsWrite + ?Sized>(mut value: u128, out: &mut W) -> io::Result<()> {
+    let mut bytes = [0u8; 19];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
     }
+    out.write_bytes(&bytes[..len])
 }
 
-impl SerializerData for isize {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Map the signed values to unsigned values, so small negative values stay short (0, -1, 1, -2, ...).
+fn zigzag(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
+
+/// A slice of u8 is written in one copy.
+impl SerializerData for u8 {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        out.write_bytes(&self.to_le_bytes())
+    }
+
+    fn serialize_slice<W: BitsWrite + ?Sized>(slice: &[Self], out: &mut W) -> io::Result<()> {
+        slice.len().serialize_into(out)?;
+        out.write_bytes(slice)
     }
 }
 
 impl SerializerData for i8 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        out.write_bytes(&self.to_le_bytes())
+    }
+
+    fn serialize_slice<W: BitsWrite + ?Sized>(slice: &[Self], out: &mut W) -> io::Result<()> {
+        serialize_bulk(slice, out, i8::to_le_bytes)
     }
 }
 
-impl SerializerData for i16 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Integers are written with the byte order of the config, or as a varint.
+macro_rules! serializer_int {
+    ($($t:ty => $to_varint:expr),*) => {
+        $(
+            impl SerializerData for $t {
+                fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+                    let config = out.config();
+                    match (config.int_encoding(), config.byte_order()) {
+                        (IntEncoding::Varint, _) => serialize_varint($to_varint(*self), out),
+                        (IntEncoding::Fixed, ByteOrder::LittleEndian) => {
+                            out.write_bytes(&self.to_le_bytes())
+                        }
+                        (IntEncoding::Fixed, ByteOrder::BigEndian) => {
+                            out.write_bytes(&self.to_be_bytes())
+                        }
+                    }
+                }
+
+                fn serialize_slice<W: BitsWrite + ?Sized>(
+                    slice: &[Self],
+                    out: &mut W,
+                ) -> io::Result<()> {
+                    let config = out.config();
+                    match (config.int_encoding(), config.byte_order()) {
+                        (IntEncoding::Varint, _) => serialize_sized(slice, out),
+                        (IntEncoding::Fixed, ByteOrder::LittleEndian) => {
+                            serialize_bulk(slice, out, <$t>::to_le_bytes)
+                        }
+                        (IntEncoding::Fixed, ByteOrder::BigEndian) => {
+                            serialize_bulk(slice, out, <$t>::to_be_bytes)
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+serializer_int!(
+    u16 => u128::from,
+    u32 => u128::from,
+    u64 => u128::from,
+    u128 => u128::from,
+    i16 => |v| zigzag(i128::from(v)),
+    i32 => |v| zigzag(i128::from(v)),
+    i64 => |v| zigzag(i128::from(v)),
+    i128 => zigzag
+);
+
+/// Floats are written with all the bits in the byte order of the config, never as a varint.
+macro_rules! serializer_float {
+    ($($t:ident => $canonical_nan:expr),*) => {
+        $(
+            impl SerializerData for $t {
+                fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+                    let config = out.config();
+                    let value = if config.canonical_nan() && self.is_nan() {
+                        $t::from_bits($canonical_nan)
+                    } else {
+                        *self
+                    };
+                    match config.byte_order() {
+                        ByteOrder::LittleEndian => out.write_bytes(&value.to_le_bytes()),
+                        ByteOrder::BigEndian => out.write_bytes(&value.to_be_bytes()),
+                    }
+                }
+
+                fn serialize_slice<W: BitsWrite + ?Sized>(
+                    slice: &[Self],
+                    out: &mut W,
+                ) -> io::Result<()> {
+                    let config = out.config();
+                    let canonical = |value: $t| {
+                        if config.canonical_nan() && value.is_nan() {
+                            $t::from_bits($canonical_nan)
+                        } else {
+                            value
+                        }
+                    };
+                    match config.byte_order() {
+                        ByteOrder::LittleEndian => {
+                            serialize_bulk(slice, out, |value| canonical(value).to_le_bytes())
+                        }
+                        ByteOrder::BigEndian => {
+                            serialize_bulk(slice, out, |value| canonical(value).to_be_bytes())
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+serializer_float!(f32 => 0x7fc0_0000, f64 => 0x7ff8_0000_0000_0000);
+
+/// Written as the seconds (u64) followed by the nanoseconds (u32).
+impl SerializerData for Duration {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_secs().serialize_into(out)?;
+        self.subsec_nanos().serialize_into(out)
     }
 }
 
-impl SerializerData for i32 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Written as the seconds from the UNIX epoch (i64, negative before 1970)
+/// followed by the nanoseconds after these seconds (u32).
+///
+/// Returns an error if the seconds do not fit in a i64.
+impl SerializerData for SystemTime {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let (secs, nanos) = match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => (i64::try_from(after.as_secs()).ok(), after.subsec_nanos()),
+            Err(e) => {
+                let before = e.duration();
+                let secs = i64::try_from(before.as_secs()).ok().map(|secs| -secs);
+                match before.subsec_nanos() {
+                    0 => (secs, 0),
+                    nanos => (
+                        secs.and_then(|secs| secs.checked_sub(1)),
+                        1_000_000_000 - nanos,
+                    ),
+                }
+            }
+        };
+        let secs = secs.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the time {self:?} is too far from the UNIX epoch"),
+            )
+        })?;
+        secs.serialize_into(out)?;
+        nanos.serialize_into(out)
     }
 }
 
-impl SerializerData for i64 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Written as a u64, so the size is the same on every architecture.
+impl SerializerData for usize {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        (*self as u64).serialize_into(out)
     }
 }
 
-impl SerializerData for i128 {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_ne_bytes().to_vec()
+/// Written as a i64, so the size is the same on every architecture.
+impl SerializerData for isize {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        (*self as i64).serialize_into(out)
     }
 }
 
+/// Non-zero integers are written like the integer.
+macro_rules! serializer_non_zero {
+    ($($t:ty),*) => {
+        $(
+            impl SerializerData for $t {
+                fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+                    self.get().serialize_into(out)
+                }
+            }
+        )*
+    };
+}
+
+serializer_non_zero!(
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32,
+    NonZeroU64,
+    NonZeroU128,
+    NonZeroUsize,
+    NonZeroI8,
+    NonZeroI16,
+    NonZeroI32,
+    NonZeroI64,
+    NonZeroI128,
+    NonZeroIsize
+);
+
+/// Atomics are written like their value, loaded with the ordering of the config.
+macro_rules! serializer_atomic {
+    ($($t:ident => $size:literal),*) => {
+        $(
+            #[cfg(target_has_atomic = $size)]
+            impl SerializerData for atomic::$t {
+                fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+                    self.load(out.config().load_ordering()).serialize_into(out)
+                }
+            }
+        )*
+    };
+}
+
+serializer_atomic!(
+    AtomicBool => "8",
+    AtomicU8 => "8",
+    AtomicU16 => "16",
+    AtomicU32 => "32",
+    AtomicU64 => "64",
+    AtomicUsize => "ptr",
+    AtomicI8 => "8",
+    AtomicI16 => "16",
+    AtomicI32 => "32",
+    AtomicI64 => "64",
+    AtomicIsize => "ptr"
+);
+
 impl SerializerData for bool {
-    fn to_data(&self) -> Vec<u8> {
-        let value = if self.clone() { 1 as u8 } else { 0 as u8 };
-        value.to_data()
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let value = if *self { 1u8 } else { 0u8 };
+        value.serialize_into(out)
     }
 }
 
+/// Written in UTF-8, from 1 to 4 bytes.
 impl SerializerData for char {
-    fn to_data(&self) -> Vec<u8> {
-        let bit = self.clone() as u8;
-        bit.to_data()
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let mut bytes = [0u8; 4];
+        out.write_bytes(self.encode_utf8(&mut bytes).as_bytes())
+    }
+}
+
+impl SerializerData for str {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.len().serialize_into(out)?;
+        out.write_bytes(self.as_bytes())
+    }
+}
+
+/// Written as the raw bytes on Unix (size and bytes, like a `str`).
+///
+/// On the other platforms the value must be valid Unicode, it is written in UTF-8,
+/// an error is returned otherwise.
+impl SerializerData for OsStr {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(self);
+        #[cfg(not(unix))]
+        let bytes = self
+            .to_str()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{self:?} is not valid Unicode"),
+                )
+            })?
+            .as_bytes();
+        bytes.len().serialize_into(out)?;
+        out.write_bytes(bytes)
+    }
+}
+
+impl SerializerData for OsString {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_os_str().serialize_into(out)
+    }
+}
+
+/// Written like an `OsStr`.
+impl SerializerData for Path {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_os_str().serialize_into(out)
+    }
+}
+
+impl SerializerData for PathBuf {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_path().serialize_into(out)
+    }
+}
+
+/// Written as the size and the bytes, without the nul terminator.
+impl SerializerData for CStr {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let bytes = self.to_bytes();
+        bytes.len().serialize_into(out)?;
+        out.write_bytes(bytes)
+    }
+}
+
+impl SerializerData for CString {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_c_str().serialize_into(out)
     }
 }
 
 impl SerializerData for String {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        res.append(&mut self.len().to_data());
-        res.append(&mut self.as_bytes().to_vec());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_str().serialize_into(out)
     }
 }
 
 impl<T: SerializerData> SerializerData for Option<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
         if let Some(value) = self {
-            let code = 1 as u8;
-            res.append(&mut code.to_data());
-            res.append(&mut value.to_data());
+            let code = 1u8;
+            code.serialize_into(out)?;
+            value.serialize_into(out)
         } else {
-            let code = 0 as u8;
-            res.append(&mut code.to_data());
+            let code = 0u8;
+            code.serialize_into(out)
+        }
+    }
+}
+
+/// `Ok` is written with the tag 0, `Err` with the tag 1.
+impl<T: SerializerData, E: SerializerData> SerializerData for Result<T, E> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        match self {
+            Ok(value) => {
+                let code = 0u8;
+                code.serialize_into(out)?;
+                value.serialize_into(out)
+            }
+            Err(error) => {
+                let code = 1u8;
+                code.serialize_into(out)?;
+                error.serialize_into(out)
+            }
+        }
+    }
+}
+
+/// Written as the start followed by the end.
+impl<T: SerializerData> SerializerData for Range<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.start.serialize_into(out)?;
+        self.end.serialize_into(out)
+    }
+}
+
+/// Written as the start followed by the end, a range already iterated is written with its bounds.
+impl<T: SerializerData> SerializerData for RangeInclusive<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.start().serialize_into(out)?;
+        self.end().serialize_into(out)
+    }
+}
+
+impl<T: SerializerData> SerializerData for RangeFrom<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.start.serialize_into(out)
+    }
+}
+
+impl<T: SerializerData> SerializerData for RangeTo<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.end.serialize_into(out)
+    }
+}
+
+impl<T: SerializerData> SerializerData for RangeToInclusive<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.end.serialize_into(out)
+    }
+}
+
+/// Nothing is written.
+impl SerializerData for RangeFull {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, _out: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// `Included` is written with the tag 0, `Excluded` with the tag 1 and `Unbounded` with the tag 2.
+impl<T: SerializerData> SerializerData for Bound<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        match self {
+            Bound::Included(value) => {
+                let code = 0u8;
+                code.serialize_into(out)?;
+                value.serialize_into(out)
+            }
+            Bound::Excluded(value) => {
+                let code = 1u8;
+                code.serialize_into(out)?;
+                value.serialize_into(out)
+            }
+            Bound::Unbounded => {
+                let code = 2u8;
+                code.serialize_into(out)
+            }
         }
-        res
+    }
+}
+
+/// `Less` is written with the tag 0, `Equal` with the tag 1 and `Greater` with the tag 2.
+impl SerializerData for Ordering {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let code: u8 = match self {
+            Ordering::Less => 0,
+            Ordering::Equal => 1,
+            Ordering::Greater => 2,
+        };
+        code.serialize_into(out)
+    }
+}
+
+/// Nothing is written.
+impl SerializerData for () {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, _out: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Nothing is written.
+impl<T: ?Sized> SerializerData for PhantomData<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, _out: &mut W) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: SerializerData> SerializerData for Reverse<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.0.serialize_into(out)
+    }
+}
+
+impl<T: SerializerData> SerializerData for Wrapping<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.0.serialize_into(out)
+    }
+}
+
+impl<T: SerializerData> SerializerData for Saturating<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.0.serialize_into(out)
+    }
+}
+
+impl<T: SerializerData> SerializerData for [T] {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        T::serialize_slice(self, out)
     }
 }
 
 impl<T: SerializerData> SerializerData for Vec<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let data = self.iter().flat_map(|e| e.to_data()).collect::<Vec<u8>>();
-        res.append(&mut data.len().to_data());
-        res.append(&mut data.clone());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        T::serialize_slice(self, out)
     }
 }
 
 impl<T: SerializerData> SerializerData for VecDeque<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let data = self.iter().flat_map(|e| e.to_data()).collect::<Vec<u8>>();
-        res.append(&mut data.len().to_data());
-        res.append(&mut data.clone());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        serialize_sized(self, out)
     }
 }
 
 impl<T: SerializerData> SerializerData for LinkedList<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let data = self.iter().flat_map(|e| e.to_data()).collect::<Vec<u8>>();
-        res.append(&mut data.len().to_data());
-        res.append(&mut data.clone());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        serialize_sized(self, out)
     }
 }
 
-impl<T: SerializerData> SerializerData for HashSet<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let data = self.iter().flat_map(|e| e.to_data()).collect::<Vec<u8>>();
-        res.append(&mut data.len().to_data());
-        res.append(&mut data.clone());
-        res
+/// Written in the order of the hashes, or sorted with [`Config::with_sorted_keys`].
+impl<T: SerializerData, S> SerializerData for HashSet<T, S> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        if out.config().sorted_keys() {
+            let elements = sort_by_encoded_key(self.iter(), |e| *e, out.config())?;
+            return serialize_sized(elements, out);
+        }
+        serialize_sized(self, out)
     }
 }
 
 impl<T: SerializerData> SerializerData for BTreeSet<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let data = self.iter().flat_map(|e| e.to_data()).collect::<Vec<u8>>();
-        res.append(&mut data.len().to_data());
-        res.append(&mut data.clone());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        serialize_sized(self, out)
     }
 }
 
 impl<T: SerializerData> SerializerData for BinaryHeap<T> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let data = self.iter().flat_map(|e| e.to_data()).collect::<Vec<u8>>();
-        res.append(&mut data.len().to_data());
-        res.append(&mut data.clone());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        serialize_sized(self, out)
     }
 }
 
-impl<K: SerializerData, V: SerializerData> SerializerData for HashMap<K, V> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let mut map = self
-            .iter()
-            .flat_map(|(key, value)| {
-                let mut merge = Vec::new();
-                merge.append(&mut key.to_data());
-                merge.append(&mut value.to_data());
-                merge
-            })
-            .collect::<Vec<u8>>();
-        res.append(&mut map.len().to_data());
-        res.append(&mut map);
-        res
+/// Written in the order of the hashes, or sorted with [`Config::with_sorted_keys`].
+impl<K: SerializerData, V: SerializerData, S> SerializerData for HashMap<K, V, S> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        if out.config().sorted_keys() {
+            let entries = sort_by_encoded_key(self.iter(), |(key, _)| *key, out.config())?;
+            return serialize_sized_map(entries, out);
+        }
+        serialize_sized_map(self, out)
+    }
+}
+
+/// # Sorted
+///
+/// `HashMap` or `HashSet` written with the keys in their `Ord` order,
+/// the data is the same as a `BTreeMap` or a `BTreeSet` with the same content.
+#[derive(Debug, Clone, Copy)]
+pub struct Sorted<'a, T: ?Sized>(pub &'a T);
+
+impl<T: SerializerData + Ord, S> SerializerData for Sorted<'_, HashSet<T, S>> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let mut elements = self.0.iter().collect::<Vec<_>>();
+        elements.sort_unstable();
+        serialize_sized(elements, out)
+    }
+}
+
+impl<K: SerializerData + Ord, V: SerializerData, S> SerializerData
+    for Sorted<'_, HashMap<K, V, S>>
+{
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        serialize_sized_map(entries, out)
     }
 }
 
 impl<K: SerializerData, V: SerializerData> SerializerData for BTreeMap<K, V> {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        let mut map = self
-            .iter()
-            .flat_map(|(key, value)| {
-                let mut merge = Vec::new();
-                merge.append(&mut key.to_data());
-                merge.append(&mut value.to_data());
-                merge
-            })
-            .collect::<Vec<u8>>();
-        res.append(&mut map.len().to_data());
-        res.append(&mut map);
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        serialize_sized_map(self, out)
+    }
+}
+
+/// Written as the 4 octets.
+impl SerializerData for Ipv4Addr {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        out.write_bytes(&self.octets())
+    }
+}
+
+/// Written as the 16 octets.
+impl SerializerData for Ipv6Addr {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        out.write_bytes(&self.octets())
+    }
+}
+
+/// `V4` is written with the tag 0, `V6` with the tag 1, followed by the octets.
+impl SerializerData for IpAddr {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        match self {
+            IpAddr::V4(ip) => {
+                let code = 0u8;
+                code.serialize_into(out)?;
+                ip.serialize_into(out)
+            }
+            IpAddr::V6(ip) => {
+                let code = 1u8;
+                code.serialize_into(out)?;
+                ip.serialize_into(out)
+            }
+        }
     }
 }
 
+/// Written as the octets followed by the port.
+impl SerializerData for SocketAddrV4 {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.ip().serialize_into(out)?;
+        self.port().serialize_into(out)
+    }
+}
+
+/// Written as the octets followed by the port, the flowinfo and the scope id.
+impl SerializerData for SocketAddrV6 {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.ip().serialize_into(out)?;
+        self.port().serialize_into(out)?;
+        self.flowinfo().serialize_into(out)?;
+        self.scope_id().serialize_into(out)
+    }
+}
+
+/// `V4` is written with the tag 0, `V6` with the tag 1, followed by the address.
 impl SerializerData for SocketAddr {
-    fn to_data(&self) -> Vec<u8> {
-        self.to_string().to_data()
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        match self {
+            SocketAddr::V4(addr) => {
+                let code = 0u8;
+                code.serialize_into(out)?;
+                addr.serialize_into(out)
+            }
+            SocketAddr::V6(addr) => {
+                let code = 1u8;
+                code.serialize_into(out)?;
+                addr.serialize_into(out)
+            }
+        }
+    }
+}
+
+/// Written as text, like the `SocketAddr` of the previous versions.
+impl SerializerData for LegacySocketAddr {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.0.to_string().serialize_into(out)
+    }
+}
+
+/// Arrays are written without size, the size is known from the type.
+impl<T: SerializerData, const N: usize> SerializerData for [T; N] {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        for e in self {
+            e.serialize_into(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tuples are written element by element, without size.
+macro_rules! serializer_tuple {
+    ($(($($t:ident $v:ident),+)),*) => {
+        $(
+            impl<$($t: SerializerData),+> SerializerData for ($($t,)+) {
+                fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+                    let ($($v,)+) = self;
+                    $($v.serialize_into(out)?;)+
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+serializer_tuple!(
+    (A a),
+    (A a, B b),
+    (A a, B b, C c),
+    (A a, B b, C c, D d),
+    (A a, B b, C c, D d, E e),
+    (A a, B b, C c, D d, E e, F f),
+    (A a, B b, C c, D d, E e, F f, G g),
+    (A a, B b, C c, D d, E e, F f, G g, H h),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o),
+    (A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l, M m, N n, O o, P p)
+);
+
+impl<T: SerializerData + ?Sized> SerializerData for Box<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_ref().serialize_into(out)
+    }
+}
+
+/// Written once per object by a [`GraphWriter`](crate::graph::GraphWriter),
+/// which keeps the object alive.
+impl<T: SerializerData + ?Sized + 'static> SerializerData for Rc<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        graph::serialize_shared(self, out)
+    }
+}
+
+/// Written once per object by a [`GraphWriter`](crate::graph::GraphWriter),
+/// which keeps the object alive.
+impl<T: SerializerData + ?Sized + 'static> SerializerData for Arc<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        graph::serialize_shared(self, out)
+    }
+}
+
+/// Returns an error if the destination is not a [`GraphWriter`](crate::graph::GraphWriter).
+impl<T: SerializerData + ?Sized + 'static> SerializerData for rc::Weak<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        graph::serialize_weak(self.upgrade().as_ref(), out)
+    }
+}
+
+/// Returns an error if the destination is not a [`GraphWriter`](crate::graph::GraphWriter).
+impl<T: SerializerData + ?Sized + 'static> SerializerData for sync::Weak<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        graph::serialize_weak(self.upgrade().as_ref(), out)
+    }
+}
+
+impl<T: SerializerData + ToOwned + ?Sized> SerializerData for Cow<'_, T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.as_ref().serialize_into(out)
+    }
+}
+
+impl<T: SerializerData + Copy> SerializerData for Cell<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.get().serialize_into(out)
+    }
+}
+
+/// Returns an error if the value is mutably borrowed.
+impl<T: SerializerData + ?Sized> SerializerData for RefCell<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        match self.try_borrow() {
+            Ok(value) => value.serialize_into(out),
+            Err(e) => Err(io::Error::other(e)),
+        }
+    }
+}
+
+/// A poisoned lock is still written, the value is the last one written before the panic.
+impl<T: SerializerData + ?Sized> SerializerData for Mutex<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let value = self.lock().unwrap_or_else(PoisonError::into_inner);
+        value.serialize_into(out)
+    }
+}
+
+/// A poisoned lock is still written, the value is the last one written before the panic.
+impl<T: SerializerData + ?Sized> SerializerData for RwLock<T> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        let value = self.read().unwrap_or_else(PoisonError::into_inner);
+        value.serialize_into(out)
     }
 }
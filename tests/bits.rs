@@ -0,0 +1,124 @@
+use serialize_bits::{
+    bits::{BitOrder, BitReader, BitWriter},
+    des::{DeserializeError, DeserializerData},
+    ser::SerializerData,
+};
+
+#[test]
+pub fn test_msb_first() {
+    let mut writer = BitWriter::new(BitOrder::MsbFirst);
+    writer.write_bits(5, 3).unwrap();
+    writer.write_bits(0xABC, 12).unwrap();
+    writer.write_bool(true);
+    assert_eq!(16, writer.bit_len());
+    let data = writer.into_bytes();
+    assert_eq!(vec![0xB5, 0x79], data);
+
+    let mut reader = BitReader::new(&data, 0, BitOrder::MsbFirst);
+    assert_eq!(Ok(5), reader.read_bits(3));
+    assert_eq!(Ok(0xABC), reader.read_bits(12));
+    assert_eq!(Ok(true), reader.read_bool());
+    assert_eq!(16, reader.position());
+}
+
+#[test]
+pub fn test_lsb_first() {
+    let mut writer = BitWriter::new(BitOrder::LsbFirst);
+    writer.write_bits(5, 3).unwrap();
+    writer.write_bits(0xABC, 12).unwrap();
+    writer.write_bool(true);
+    let data = writer.into_bytes();
+    assert_eq!(vec![0xE5, 0xD5], data);
+
+    let mut reader = BitReader::new(&data, 0, BitOrder::LsbFirst);
+    assert_eq!(Ok(5), reader.read_bits(3));
+    assert_eq!(Ok(0xABC), reader.read_bits(12));
+    assert_eq!(Ok(true), reader.read_bool());
+}
+
+#[test]
+pub fn test_signed() {
+    for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+        let mut writer = BitWriter::new(order);
+        writer.write_signed(-3, 3).unwrap();
+        writer.write_signed(3, 3).unwrap();
+        writer.write_signed(-4, 3).unwrap();
+        writer.write_signed(i64::MIN, 64).unwrap();
+        writer.write_signed(-1, 7).unwrap();
+        assert_eq!(80, writer.bit_len());
+        let data = writer.into_bytes();
+        assert_eq!(10, data.len());
+
+        let mut reader = BitReader::new(&data, 0, order);
+        assert_eq!(Ok(-3), reader.read_signed(3));
+        assert_eq!(Ok(3), reader.read_signed(3));
+        assert_eq!(Ok(-4), reader.read_signed(3));
+        assert_eq!(Ok(i64::MIN), reader.read_signed(64));
+        assert_eq!(Ok(-1), reader.read_signed(7));
+    }
+}
+
+#[test]
+pub fn test_padding() {
+    let mut writer = BitWriter::new(BitOrder::MsbFirst);
+    writer.write_bool(true);
+    writer.write_bits(0, 0).unwrap();
+    writer.write_bits(1, 2).unwrap();
+    assert_eq!(3, writer.bit_len());
+    assert_eq!(vec![0b1010_0000], writer.into_bytes());
+}
+
+#[test]
+pub fn test_value_too_large() {
+    let mut writer = BitWriter::new(BitOrder::MsbFirst);
+    assert!(writer.write_bits(8, 3).is_err());
+    assert!(writer.write_signed(4, 3).is_err());
+    assert!(writer.write_signed(-5, 3).is_err());
+    assert!(writer.write_bits(u64::MAX, 64).is_ok());
+    assert_eq!(64, writer.bit_len());
+}
+
+#[test]
+pub fn test_truncated() {
+    let mut reader = BitReader::new(&[0xff], 0, BitOrder::MsbFirst);
+    assert_eq!(Ok(0x7f), reader.read_bits(7));
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "bits",
+            needed: 3,
+            available: 1,
+        }),
+        reader.read_bits(3)
+    );
+    assert_eq!(Ok(true), reader.read_bool());
+    assert!(reader.read_bool().is_err());
+}
+
+#[test]
+pub fn test_with_byte_api() {
+    // A telemetry frame : 3 bits tag, 12 bits counter, 1 bit flag, then a byte aligned String.
+    let mut writer = BitWriter::new(BitOrder::MsbFirst);
+    writer.write_bits(6, 3).unwrap();
+    writer.write_bits(4000, 12).unwrap();
+    writer.write_bool(false);
+    writer.write_bits(1, 1).unwrap();
+    let mut data = Vec::new();
+    writer.serialize_into(&mut data).unwrap();
+    String::from("ok").serialize_into(&mut data).unwrap();
+    assert_eq!(3 + 8 + 2, data.len());
+
+    let mut reader = BitReader::new(&data, 0, BitOrder::MsbFirst);
+    assert_eq!(Ok(6), reader.read_bits(3));
+    assert_eq!(Ok(4000), reader.read_bits(12));
+    assert_eq!(Ok(false), reader.read_bool());
+    assert_eq!(Ok(1), reader.read_bits(1));
+    assert_eq!(3, reader.byte_index());
+    assert_eq!(
+        (String::from("ok"), 13),
+        String::from_data(&data, reader.byte_index())
+    );
+
+    // LSB first over whole bytes is the little-endian byte order.
+    let mut reader = BitReader::new(&data, 3, BitOrder::LsbFirst);
+    assert_eq!(Ok(2), reader.read_bits(64));
+}
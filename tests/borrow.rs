@@ -0,0 +1,136 @@
+use std::{borrow::Cow, io, ops::Range};
+
+use serialize_bits::{
+    config::{ByteOrder, Config, IntEncoding},
+    des::{BitsRead, BorrowDeserializerData, ConfigReader, DeserializeError, DeserializerData},
+    ser::{BitsWrite, SerializerData},
+};
+
+/// Struct mixing borrowed and owned fields.
+#[derive(Debug, PartialEq)]
+struct Entry<'a> {
+    id: u32,
+    name: &'a str,
+    tags: Vec<String>,
+    payload: Cow<'a, [u8]>,
+    comment: Option<Cow<'a, str>>,
+}
+
+impl SerializerData for Entry<'_> {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.id.serialize_into(out)?;
+        self.name.serialize_into(out)?;
+        self.tags.serialize_into(out)?;
+        self.payload.serialize_into(out)?;
+        self.comment.serialize_into(out)
+    }
+}
+
+impl<'de> BorrowDeserializerData<'de> for Entry<'de> {
+    fn try_borrow_from_data<R: BitsRead + ?Sized>(
+        data: &'de R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError> {
+        let (id, index) = u32::try_from_data(data, index)?;
+        let (name, index) = <&str>::try_borrow_from_data(data, index)?;
+        let (tags, index) = Vec::try_from_data(data, index)?;
+        let (payload, index) = Cow::try_borrow_from_data(data, index)?;
+        let (comment, index) = Option::try_borrow_from_data(data, index)?;
+        Ok((
+            Self {
+                id,
+                name,
+                tags,
+                payload,
+                comment,
+            },
+            index,
+        ))
+    }
+}
+
+fn entry() -> Entry<'static> {
+    Entry {
+        id: 7,
+        name: "seven",
+        tags: vec![String::from("a"), String::from("b")],
+        payload: Cow::Owned(vec![1, 2, 3]),
+        comment: Some(Cow::Borrowed("lucky")),
+    }
+}
+
+fn in_data(data: &[u8], ptr: *const u8) -> bool {
+    let Range { start, end } = data.as_ptr_range();
+    start <= ptr && ptr < end
+}
+
+#[test]
+fn test_borrow_str() {
+    let data = String::from("borrowed").to_data();
+    let (value, index) = <&str>::borrow_from_data(&data, 0);
+    assert_eq!(("borrowed", 16), (value, index));
+    assert!(in_data(&data, value.as_ptr()));
+
+    let (value, _) = Cow::<str>::borrow_from_data(&data, 0);
+    assert!(matches!(value, Cow::Borrowed("borrowed")));
+}
+
+#[test]
+fn test_borrow_bytes() {
+    let data = vec![1_u8, 2, 3].to_data();
+    let (value, index) = <&[u8]>::borrow_from_data(&data, 0);
+    assert_eq!((&[1_u8, 2, 3][..], 11), (value, index));
+    assert!(in_data(&data, value.as_ptr()));
+
+    let (value, _) = Cow::<[u8]>::borrow_from_data(&data[..], 0);
+    assert!(matches!(value, Cow::Borrowed([1, 2, 3])));
+}
+
+#[test]
+fn test_borrow_struct() {
+    let value = entry();
+    let data = value.to_data();
+    let (res, index) = Entry::borrow_from_data(&data, 0);
+    assert_eq!(data.len(), index);
+    assert_eq!(value, res);
+    assert!(in_data(&data, res.name.as_ptr()));
+    assert!(in_data(&data, res.payload.as_ptr()));
+    assert!(matches!(res.comment, Some(Cow::Borrowed("lucky"))));
+}
+
+#[test]
+fn test_borrow_config() {
+    let config = Config::new()
+        .with_byte_order(ByteOrder::BigEndian)
+        .with_int_encoding(IntEncoding::Varint);
+    let value = entry();
+    let data = value.to_data_with(config);
+    let reader = ConfigReader::new(&data, config);
+    let (res, index) = Entry::borrow_from_data(&reader, 0);
+    assert_eq!(data.len(), index);
+    assert_eq!(value, res);
+    assert!(in_data(&data, res.name.as_ptr()));
+}
+
+#[test]
+fn test_borrow_invalid() {
+    assert_eq!(
+        Err(DeserializeError::InvalidUtf8 { type_name: "&str" }),
+        <&str>::try_borrow_from_data(&[2, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xfe], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "&[u8]",
+            needed: 3,
+            available: 1,
+        }),
+        <&[u8]>::try_borrow_from_data(&[3, 0, 0, 0, 0, 0, 0, 0, 1], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Option",
+            tag: 2,
+        }),
+        Option::<&str>::try_borrow_from_data(&[2], 0)
+    );
+}
@@ -1,4 +1,9 @@
-use serialize_bits::{des::DeserializerData, ser::SerializerData};
+use std::io;
+
+use serialize_bits::{
+    des::{BitsRead, DeserializeError, DeserializerData},
+    ser::{BitsWrite, SerializerData},
+};
 
 #[derive(Clone, Debug, PartialEq)]
 struct City {
@@ -7,22 +12,24 @@ struct City {
 }
 
 impl SerializerData for City {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        res.append(&mut self.id.to_data());
-        res.append(&mut self.name.to_data());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.id.serialize_into(out)?;
+        self.name.serialize_into(out)?;
+        Ok(())
     }
 }
 
 impl DeserializerData for City {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (id, index) = i64::from_data(data, index);
-        let (name, index) = String::from_data(data, index);
-        (Self { id, name }, index)
+        let (id, index) = i64::try_from_data(data, index)?;
+        let (name, index) = String::try_from_data(data, index)?;
+        Ok((Self { id, name }, index))
     }
 }
 
@@ -35,26 +42,28 @@ struct Address {
 }
 
 impl SerializerData for Address {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        res.append(&mut self.id.to_data());
-        res.append(&mut self.street.to_data());
-        res.append(&mut self.postal_code.to_data());
-        res.append(&mut self.city.to_data());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.id.serialize_into(out)?;
+        self.street.serialize_into(out)?;
+        self.postal_code.serialize_into(out)?;
+        self.city.serialize_into(out)?;
+        Ok(())
     }
 }
 
 impl DeserializerData for Address {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (id, index) = i64::from_data(data, index);
-        let (street, index) = String::from_data(data, index);
-        let (postal_code, index) = String::from_data(data, index);
-        let (city, index) = City::from_data(data, index);
-        (
+        let (id, index) = i64::try_from_data(data, index)?;
+        let (street, index) = String::try_from_data(data, index)?;
+        let (postal_code, index) = String::try_from_data(data, index)?;
+        let (city, index) = City::try_from_data(data, index)?;
+        Ok((
             Self {
                 id,
                 street,
@@ -62,7 +71,7 @@ impl DeserializerData for Address {
                 city,
             },
             index,
-        )
+        ))
     }
 }
 
@@ -74,41 +83,46 @@ enum Genre {
 }
 
 impl SerializerData for Genre {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
         match self {
             Self::Male => {
-                let code = 1 as u8;
-                res.append(&mut code.to_data());
+                let code = 1u8;
+                code.serialize_into(out)?;
             }
             Self::Female => {
-                let code = 2 as u8;
-                res.append(&mut code.to_data());
+                let code = 2u8;
+                code.serialize_into(out)?;
             }
             Self::Custom(v) => {
-                let code = 3 as u8;
-                res.append(&mut code.to_data());
-                res.append(&mut v.to_data());
+                let code = 3u8;
+                code.serialize_into(out)?;
+                v.serialize_into(out)?;
             }
         }
-        res
+        Ok(())
     }
 }
 
 impl DeserializerData for Genre {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (code, index) = u8::from_data(data, index);
+        let (code, index) = u8::try_from_data(data, index)?;
         match code {
-            1 => (Self::Male, index),
-            2 => (Self::Female, index),
+            1 => Ok((Self::Male, index)),
+            2 => Ok((Self::Female, index)),
             3 => {
-                let (value, index) = String::from_data(data, index);
-                (Self::Custom(value), index)
+                let (value, index) = String::try_from_data(data, index)?;
+                Ok((Self::Custom(value), index))
             }
-            _ => panic!("Invalid code !"),
+            tag => Err(DeserializeError::InvalidTag {
+                type_name: "Genre",
+                tag,
+            }),
         }
     }
 }
@@ -122,26 +136,28 @@ struct Person {
 }
 
 impl SerializerData for Person {
-    fn to_data(&self) -> Vec<u8> {
-        let mut res = Vec::new();
-        res.append(&mut self.id.to_data());
-        res.append(&mut self.name.to_data());
-        res.append(&mut self.genre.to_data());
-        res.append(&mut self.addresses.to_data());
-        res
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.id.serialize_into(out)?;
+        self.name.serialize_into(out)?;
+        self.genre.serialize_into(out)?;
+        self.addresses.serialize_into(out)?;
+        Ok(())
     }
 }
 
 impl DeserializerData for Person {
-    fn from_data(data: &Vec<u8>, index: usize) -> (Self, usize)
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
     where
         Self: Sized,
     {
-        let (id, index) = i32::from_data(data, index);
-        let (name, index) = String::from_data(data, index);
-        let (genre, index) = Genre::from_data(data, index);
-        let (addresses, index) = Vec::from_data(data, index);
-        (
+        let (id, index) = i32::try_from_data(data, index)?;
+        let (name, index) = String::try_from_data(data, index)?;
+        let (genre, index) = Genre::try_from_data(data, index)?;
+        let (addresses, index) = Vec::try_from_data(data, index)?;
+        Ok((
             Self {
                 id,
                 name,
@@ -149,7 +165,7 @@ impl DeserializerData for Person {
                 addresses,
             },
             index,
-        )
+        ))
     }
 }
 
@@ -194,3 +210,53 @@ fn test_struct() {
     let des = Person::from_data(&data, 0);
     assert_eq!((person, 188), des);
 }
+
+#[test]
+fn test_struct_invalid_tag() {
+    let data = vec![35, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9];
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Genre",
+            tag: 9,
+        }),
+        Person::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+fn test_struct_truncated() {
+    let city = City {
+        id: 44,
+        name: String::from("NANTES"),
+    };
+    let data = city.to_data();
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "String",
+            needed: 6,
+            available: 3,
+        }),
+        City::try_from_data(&data[..19], 0)
+    );
+}
+
+#[test]
+fn test_struct_large() {
+    let city = City {
+        id: 44,
+        name: String::from("NANTES"),
+    };
+    let addresses = (0..200_000)
+        .map(|id| Address {
+            id,
+            street: format!("{id} rue Jean Jaurès"),
+            postal_code: String::from("44000"),
+            city: city.clone(),
+        })
+        .collect::<Vec<Address>>();
+    let data = addresses.to_data();
+    assert!(data.len() > 10_000_000);
+    let (res, index) = Vec::<Address>::try_from_data(&data, 0).unwrap();
+    assert_eq!(data.len(), index);
+    assert_eq!(addresses, res);
+}
@@ -0,0 +1,455 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    rc::{self, Rc},
+    sync::{self, Arc},
+};
+
+use serialize_bits::{
+    config::Config,
+    des::{BitsRead, DeserializeError, DeserializerData},
+    graph::{GraphReader, GraphWriter},
+    ser::{BitsWrite, SerializerData},
+};
+
+fn write<T: SerializerData>(value: &T) -> Vec<u8> {
+    let mut out = GraphWriter::new(Vec::new(), Config::default());
+    value.serialize_into(&mut out).unwrap();
+    out.into_inner()
+}
+
+fn read<T: DeserializerData>(data: &[u8]) -> Result<T, DeserializeError> {
+    let reader = GraphReader::new(data, Config::default());
+    let (value, index) = T::try_from_data(&reader, 0)?;
+    assert_eq!(data.len(), index);
+    Ok(value)
+}
+
+#[derive(Debug, PartialEq)]
+struct City {
+    name: String,
+}
+
+impl SerializerData for City {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.name.serialize_into(out)
+    }
+}
+
+impl DeserializerData for City {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (name, index) = String::try_from_data(data, index)?;
+        Ok((Self { name }, index))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Address {
+    street: String,
+    city: Rc<City>,
+}
+
+impl SerializerData for Address {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.street.serialize_into(out)?;
+        self.city.serialize_into(out)
+    }
+}
+
+impl DeserializerData for Address {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (street, index) = String::try_from_data(data, index)?;
+        let (city, index) = Rc::try_from_data(data, index)?;
+        Ok((Self { street, city }, index))
+    }
+}
+
+/// Tree with a `Weak` to the parent, as in the Rust book.
+#[derive(Debug)]
+struct Node {
+    name: String,
+    parent: RefCell<rc::Weak<Node>>,
+    children: RefCell<Vec<Rc<Node>>>,
+}
+
+impl Node {
+    fn new(name: &str) -> Rc<Self> {
+        Rc::new(Self {
+            name: String::from(name),
+            parent: RefCell::new(rc::Weak::new()),
+            children: RefCell::new(Vec::new()),
+        })
+    }
+
+    fn add(parent: &Rc<Self>, child: Rc<Self>) {
+        *child.parent.borrow_mut() = Rc::downgrade(parent);
+        parent.children.borrow_mut().push(child);
+    }
+}
+
+impl SerializerData for Node {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.name.serialize_into(out)?;
+        self.parent.serialize_into(out)?;
+        self.children.serialize_into(out)
+    }
+}
+
+impl DeserializerData for Node {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (name, index) = String::try_from_data(data, index)?;
+        let (parent, index) = RefCell::try_from_data(data, index)?;
+        let (children, index) = RefCell::try_from_data(data, index)?;
+        Ok((
+            Self {
+                name,
+                parent,
+                children,
+            },
+            index,
+        ))
+    }
+}
+
+#[derive(Debug)]
+struct Link {
+    next: RefCell<Option<Rc<Link>>>,
+}
+
+impl SerializerData for Link {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.next.serialize_into(out)
+    }
+}
+
+impl DeserializerData for Link {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (next, index) = RefCell::try_from_data(data, index)?;
+        Ok((Self { next }, index))
+    }
+}
+
+#[derive(Debug)]
+struct Service {
+    name: String,
+    this: sync::Weak<Service>,
+}
+
+impl SerializerData for Service {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.name.serialize_into(out)?;
+        self.this.serialize_into(out)
+    }
+}
+
+impl DeserializerData for Service {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (name, index) = String::try_from_data(data, index)?;
+        let (this, index) = sync::Weak::try_from_data(data, index)?;
+        Ok((Self { name, this }, index))
+    }
+}
+
+/// Struct with a `Weak` of another type than itself.
+#[derive(Debug)]
+struct Holder {
+    weak: rc::Weak<String>,
+}
+
+impl SerializerData for Holder {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.weak.serialize_into(out)
+    }
+}
+
+impl DeserializerData for Holder {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let (weak, index) = rc::Weak::try_from_data(data, index)?;
+        Ok((Self { weak }, index))
+    }
+}
+
+thread_local! {
+    static FLAKY_READS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Struct with a `Weak` to itself, only read without error the first time.
+#[derive(Debug)]
+struct Flaky {
+    this: rc::Weak<Flaky>,
+}
+
+impl SerializerData for Flaky {
+    fn serialize_into<W: BitsWrite + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        self.this.serialize_into(out)
+    }
+}
+
+impl DeserializerData for Flaky {
+    fn try_from_data<R: BitsRead + ?Sized>(
+        data: &R,
+        index: usize,
+    ) -> Result<(Self, usize), DeserializeError>
+    where
+        Self: Sized,
+    {
+        let reads = FLAKY_READS.with(|reads| reads.replace(reads.get() + 1));
+        if reads > 0 {
+            return Err(DeserializeError::OutOfRange { type_name: "Flaky" });
+        }
+        let (this, index) = rc::Weak::try_from_data(data, index)?;
+        Ok((Self { this }, index))
+    }
+}
+
+#[test]
+fn test_shared_written_once() {
+    let a = Rc::new(7u32);
+    let b = Rc::new(9u32);
+    let data = write(&vec![a.clone(), a, b]);
+    assert_eq!(
+        vec![
+            19, 0, 0, 0, 0, 0, 0, 0, // size
+            1, 7, 0, 0, 0, // new object 0
+            2, 0, 0, 0, 0, 0, 0, 0, 0, // reference to object 0
+            1, 9, 0, 0, 0, // new object 1
+        ],
+        data
+    );
+    let values: Vec<Rc<u32>> = read(&data).unwrap();
+    assert_eq!(
+        vec![7, 7, 9],
+        values.iter().map(|v| **v).collect::<Vec<_>>()
+    );
+    assert!(Rc::ptr_eq(&values[0], &values[1]));
+    assert!(!Rc::ptr_eq(&values[0], &values[2]));
+}
+
+#[test]
+fn test_shared_struct() {
+    let paris = Rc::new(City {
+        name: String::from("Paris"),
+    });
+    let addresses = vec![
+        Address {
+            street: String::from("Rue de Rivoli"),
+            city: paris.clone(),
+        },
+        Address {
+            street: String::from("Avenue Foch"),
+            city: paris,
+        },
+    ];
+    let data = write(&addresses);
+    assert!(data.len() < addresses.to_data().len());
+    let res: Vec<Address> = read(&data).unwrap();
+    assert_eq!(addresses, res);
+    assert!(Rc::ptr_eq(&res[0].city, &res[1].city));
+}
+
+#[test]
+fn test_shared_arc_str() {
+    let name: Arc<str> = Arc::from("shared");
+    let data = write(&(name.clone(), name));
+    let (a, b): (Arc<str>, Arc<str>) = read(&data).unwrap();
+    assert_eq!("shared", &*a);
+    assert!(Arc::ptr_eq(&a, &b));
+}
+
+#[test]
+fn test_writer_reused() {
+    let mut out = GraphWriter::new(Vec::new(), Config::default());
+    for value in 1..=3u32 {
+        Rc::new(value).serialize_into(&mut out).unwrap();
+    }
+    let data = out.into_inner();
+    assert_eq!(vec![1, 1, 0, 0, 0, 1, 2, 0, 0, 0, 1, 3, 0, 0, 0], data);
+    let reader = GraphReader::new(&data, Config::default());
+    let (values, index) = <(Rc<u32>, Rc<u32>, Rc<u32>)>::try_from_data(&reader, 0).unwrap();
+    assert_eq!(15, index);
+    assert_eq!((1, 2, 3), (*values.0, *values.1, *values.2));
+}
+
+#[test]
+fn test_weak_parent() {
+    let root = Node::new("root");
+    let leaf = Node::new("leaf");
+    Node::add(&root, Node::new("branch"));
+    Node::add(&root.children.borrow()[0], leaf.clone());
+    Node::add(&root, leaf);
+
+    let res: Rc<Node> = read(&write(&root)).unwrap();
+    assert_eq!("root", res.name);
+    assert!(res.parent.borrow().upgrade().is_none());
+    let children = res.children.borrow();
+    assert_eq!(2, children.len());
+    let branch = &children[0];
+    assert_eq!("branch", branch.name);
+    assert!(Rc::ptr_eq(&res, &branch.parent.borrow().upgrade().unwrap()));
+    let leaf = &branch.children.borrow()[0];
+    assert_eq!("leaf", leaf.name);
+    assert!(Rc::ptr_eq(leaf, &children[1]));
+    assert!(Rc::ptr_eq(&res, &leaf.parent.borrow().upgrade().unwrap()));
+}
+
+#[test]
+fn test_weak_self() {
+    let service = Arc::new_cyclic(|this| Service {
+        name: String::from("api"),
+        this: this.clone(),
+    });
+    let res: Vec<Arc<Service>> = read(&write(&vec![service.clone(), service])).unwrap();
+    assert_eq!("api", res[0].name);
+    assert!(Arc::ptr_eq(&res[0], &res[1]));
+    assert!(Arc::ptr_eq(&res[0], &res[0].this.upgrade().unwrap()));
+}
+
+#[test]
+fn test_weak_dropped() {
+    let weak = Rc::downgrade(&Rc::new(5u8));
+    let data = write(&weak);
+    assert_eq!(vec![0], data);
+    let res: rc::Weak<u8> = read(&data).unwrap();
+    assert!(res.upgrade().is_none());
+}
+
+#[test]
+fn test_weak_only() {
+    let value = Rc::new(5u8);
+    let res: rc::Weak<u8> = read(&write(&Rc::downgrade(&value))).unwrap();
+    // The reader keeps the object, it is dropped with the reader
+    assert!(res.upgrade().is_none());
+}
+
+#[test]
+fn test_weak_without_graph() {
+    let value = Rc::new(5u8);
+    let mut out = Vec::new();
+    let err = Rc::downgrade(&value).serialize_into(&mut out).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!(
+        DeserializeError::GraphRequired { type_name: "Weak" },
+        rc::Weak::<u8>::try_from_data(&[1u8, 5], 0).unwrap_err()
+    );
+}
+
+#[test]
+fn test_strong_cycle() {
+    let link = Rc::new(Link {
+        next: RefCell::new(None),
+    });
+    *link.next.borrow_mut() = Some(link.clone());
+    let data = write(&link);
+    link.next.borrow_mut().take();
+    assert_eq!(
+        DeserializeError::InvalidReference {
+            type_name: "Rc",
+            id: 0
+        },
+        read::<Rc<Link>>(&data).unwrap_err()
+    );
+}
+
+#[test]
+fn test_invalid_reference() {
+    let mut unknown = vec![2u8];
+    unknown.extend(5usize.to_data());
+    assert_eq!(
+        DeserializeError::InvalidReference {
+            type_name: "Rc",
+            id: 5
+        },
+        read::<Rc<u32>>(&unknown).unwrap_err()
+    );
+
+    let value = Rc::new(1u32);
+    let mut other_type = write(&(value.clone(), value));
+    other_type.truncate(5);
+    other_type.push(2);
+    other_type.extend(0usize.to_data());
+    assert_eq!(
+        DeserializeError::InvalidReference {
+            type_name: "Rc",
+            id: 0
+        },
+        read::<(Rc<u32>, Rc<u64>)>(&other_type).unwrap_err()
+    );
+
+    assert_eq!(
+        DeserializeError::InvalidTag {
+            type_name: "Rc",
+            tag: 3
+        },
+        read::<Rc<u32>>(&[3]).unwrap_err()
+    );
+}
+
+#[test]
+fn test_invalid_weak_in_progress() {
+    // The Weak refers to the Holder being read, not to a String.
+    let data = [1, 2, 0, 0, 0, 0, 0, 0, 0, 0];
+    assert_eq!(
+        DeserializeError::InvalidReference {
+            type_name: "Weak",
+            id: 0
+        },
+        read::<Rc<Holder>>(&data).unwrap_err()
+    );
+}
+
+#[test]
+fn test_cycle_read_again_with_error() {
+    let flaky = Rc::new_cyclic(|this| Flaky { this: this.clone() });
+    let data = write(&flaky);
+    assert_eq!(
+        DeserializeError::OutOfRange { type_name: "Flaky" },
+        read::<Rc<Flaky>>(&data).unwrap_err()
+    );
+    assert_eq!(2, FLAKY_READS.with(Cell::get));
+}
+
+#[test]
+fn test_truncated_cycle() {
+    let root = Node::new("root");
+    Node::add(&root, Node::new("child"));
+    let data = write(&root);
+    for len in 0..data.len() {
+        assert!(read::<Rc<Node>>(&data[..len]).is_err());
+    }
+}
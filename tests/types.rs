@@ -1,21 +1,84 @@
 use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    cmp::{Ordering, Reverse},
     collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
-    net::SocketAddr,
+    ffi::{CString, OsStr, OsString},
+    hash::{BuildHasherDefault, Hasher, RandomState},
+    io,
+    marker::PhantomData,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num::{NonZeroI32, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping},
+    ops::{Bound, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{self, AtomicBool, AtomicI16, AtomicIsize, AtomicU64, AtomicUsize},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use serialize_bits::{des::DeserializerData, ser::SerializerData};
+use serialize_bits::{
+    config::{ByteOrder, Config, IntEncoding, LengthPrefix},
+    des::{ConfigReader, DeserializeError, DeserializerData, LegacySocketAddr},
+    ser::{BitsWrite, ConfigWriter, SerializerData, Sorted},
+};
+
+const BIG_ENDIAN: Config = Config::new().with_byte_order(ByteOrder::BigEndian);
+const VARINT: Config = Config::new().with_int_encoding(IntEncoding::Varint);
+const COUNT: Config = Config::new().with_length_prefix(LengthPrefix::Count);
 
 #[test]
 pub fn test_usize() {
-    let size = 2034 as usize;
+    let size = 2034_usize;
     let data = size.to_data();
     assert_eq!(vec![242, 7, 0, 0, 0, 0, 0, 0], data);
     assert_eq!((size, 8), usize::from_data(&data, 0));
 }
 
+#[test]
+pub fn test_usize_fixed_width() {
+    let data = usize::MAX.to_data();
+    assert_eq!(8, data.len());
+    assert_eq!(u64::MAX as usize, usize::MAX);
+    assert_eq!(
+        vec![0, 0, 0, 0, 0, 0, 0, 1],
+        1_usize.to_data_with(BIG_ENDIAN)
+    );
+    assert_eq!(
+        vec![255, 255, 255, 255, 255, 255, 255, 255],
+        (-1_isize).to_data()
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u64",
+            needed: 8,
+            available: 4,
+        }),
+        usize::try_from_data(&[242, 7, 0, 0], 0)
+    );
+}
+
+#[cfg(target_pointer_width = "32")]
+#[test]
+pub fn test_usize_out_of_range() {
+    let data = (u32::MAX as u64 + 1).to_data();
+    assert_eq!(
+        Err(DeserializeError::OutOfRange { type_name: "usize" }),
+        usize::try_from_data(&data, 0)
+    );
+    let data = (i32::MIN as i64 - 1).to_data();
+    assert_eq!(
+        Err(DeserializeError::OutOfRange { type_name: "isize" }),
+        isize::try_from_data(&data, 0)
+    );
+}
+
 #[test]
 pub fn test_u8() {
-    let size = 234 as u8;
+    let size = 234_u8;
     let data = size.to_data();
     assert_eq!(vec![234], data);
     assert_eq!((size, 1), u8::from_data(&data, 0));
@@ -23,7 +86,7 @@ pub fn test_u8() {
 
 #[test]
 pub fn test_u16() {
-    let size = 555 as u16;
+    let size = 555_u16;
     let data = size.to_data();
     assert_eq!(vec![43, 2], data);
     assert_eq!((size, 2), u16::from_data(&data, 0));
@@ -31,7 +94,7 @@ pub fn test_u16() {
 
 #[test]
 pub fn test_u32() {
-    let size = 50505 as u32;
+    let size = 50505_u32;
     let data = size.to_data();
     assert_eq!(vec![73, 197, 0, 0], data);
     assert_eq!((size, 4), u32::from_data(&data, 0));
@@ -39,7 +102,7 @@ pub fn test_u32() {
 
 #[test]
 pub fn test_u64() {
-    let size = 980765 as u64;
+    let size = 980765_u64;
     let data = size.to_data();
     assert_eq!(vec![29, 247, 14, 0, 0, 0, 0, 0], data);
     assert_eq!((size, 8), u64::from_data(&data, 0));
@@ -47,7 +110,7 @@ pub fn test_u64() {
 
 #[test]
 pub fn test_u128() {
-    let size = 1234567890 as u128;
+    let size = 1234567890_u128;
     let data = size.to_data();
     assert_eq!(
         vec![210, 2, 150, 73, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
@@ -58,7 +121,7 @@ pub fn test_u128() {
 
 #[test]
 pub fn test_isize() {
-    let size = 2034 as isize;
+    let size = 2034_isize;
     let data = size.to_data();
     assert_eq!(vec![242, 7, 0, 0, 0, 0, 0, 0], data);
     assert_eq!((size, 8), isize::from_data(&data, 0));
@@ -66,7 +129,7 @@ pub fn test_isize() {
 
 #[test]
 pub fn test_i8() {
-    let size = 120 as i8;
+    let size = 120_i8;
     let data = size.to_data();
     assert_eq!(vec![120], data);
     assert_eq!((size, 1), i8::from_data(&data, 0));
@@ -74,7 +137,7 @@ pub fn test_i8() {
 
 #[test]
 pub fn test_i16() {
-    let size = 555 as i16;
+    let size = 555_i16;
     let data = size.to_data();
     assert_eq!(vec![43, 2], data);
     assert_eq!((size, 2), i16::from_data(&data, 0));
@@ -82,7 +145,7 @@ pub fn test_i16() {
 
 #[test]
 pub fn test_i32() {
-    let size = 50505 as i32;
+    let size = 50505_i32;
     let data = size.to_data();
     assert_eq!(vec![73, 197, 0, 0], data);
     assert_eq!((size, 4), i32::from_data(&data, 0));
@@ -90,7 +153,7 @@ pub fn test_i32() {
 
 #[test]
 pub fn test_i64() {
-    let size = 980765 as i64;
+    let size = 980765_i64;
     let data = size.to_data();
     assert_eq!(vec![29, 247, 14, 0, 0, 0, 0, 0], data);
     assert_eq!((size, 8), i64::from_data(&data, 0));
@@ -98,7 +161,7 @@ pub fn test_i64() {
 
 #[test]
 pub fn test_i128() {
-    let size = 1234567890 as i128;
+    let size = 1234567890_i128;
     let data = size.to_data();
     assert_eq!(
         vec![210, 2, 150, 73, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
@@ -107,6 +170,240 @@ pub fn test_i128() {
     assert_eq!((size, 16), i128::from_data(&data, 0));
 }
 
+#[test]
+pub fn test_non_zero() {
+    let value = NonZeroU32::new(555).unwrap();
+    let data = value.to_data();
+    assert_eq!(555_u32.to_data(), data);
+    assert_eq!((value, 4), NonZeroU32::from_data(&data, 0));
+
+    let value = NonZeroI32::new(-2).unwrap();
+    let data = value.to_data_with(VARINT);
+    assert_eq!(vec![3], data);
+    assert_eq!(
+        Ok((value, 1)),
+        NonZeroI32::try_from_data_with(&data, 0, VARINT)
+    );
+
+    let value = Some(NonZeroU64::new(7).unwrap());
+    let data = value.to_data();
+    assert_eq!(vec![1, 7, 0, 0, 0, 0, 0, 0, 0], data);
+    assert_eq!((value, 9), Option::from_data(&data, 0));
+}
+
+#[test]
+pub fn test_non_zero_invalid() {
+    assert_eq!(
+        Err(DeserializeError::Zero {
+            type_name: "NonZeroU8"
+        }),
+        NonZeroU8::try_from_data(&[0], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::Zero {
+            type_name: "NonZeroUsize"
+        }),
+        NonZeroUsize::try_from_data(&0_usize.to_data(), 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u32",
+            needed: 4,
+            available: 2,
+        }),
+        NonZeroU32::try_from_data(&[1, 0], 0)
+    );
+}
+
+#[test]
+pub fn test_f32() {
+    let value = 1.5_f32;
+    let data = value.to_data();
+    assert_eq!(vec![0, 0, 192, 63], data);
+    assert_eq!((value, 4), f32::from_data(&data, 0));
+    assert_eq!(vec![63, 192, 0, 0], value.to_data_with(BIG_ENDIAN));
+    assert_eq!(vec![0, 0, 192, 63], value.to_data_with(VARINT));
+    for bits in [
+        0x0000_0000, // 0.0
+        0x8000_0000, // -0.0
+        0x7f80_0000, // inf
+        0xff80_0000, // -inf
+        0x0000_0001, // subnormal
+        0x7fc0_0000, // quiet NaN
+        0x7fa0_0001, // signaling NaN with payload
+        0xffc0_1234, // negative NaN with payload
+    ] {
+        let value = f32::from_bits(bits);
+        let (res, index) = f32::from_data(&value.to_data(), 0);
+        assert_eq!((bits, 4), (res.to_bits(), index));
+        let (res, _) =
+            f32::try_from_data_with(&value.to_data_with(BIG_ENDIAN), 0, BIG_ENDIAN).unwrap();
+        assert_eq!(bits, res.to_bits());
+    }
+}
+
+#[test]
+pub fn test_f64() {
+    let value = -1234.5678_f64;
+    let data = value.to_data();
+    assert_eq!(vec![173, 250, 92, 109, 69, 74, 147, 192], data);
+    assert_eq!((value, 8), f64::from_data(&data, 0));
+    assert_eq!(
+        vec![192, 147, 74, 69, 109, 92, 250, 173],
+        value.to_data_with(BIG_ENDIAN)
+    );
+    for bits in [
+        0x0000_0000_0000_0000,
+        0x8000_0000_0000_0000,
+        0x7ff0_0000_0000_0000,
+        0xfff0_0000_0000_0000,
+        0x7ff8_0000_0000_0000,
+        0x7ff4_0000_0000_0001,
+        0xfff8_dead_beef_0000,
+    ] {
+        let value = f64::from_bits(bits);
+        let (res, index) = f64::from_data(&value.to_data(), 0);
+        assert_eq!((bits, 8), (res.to_bits(), index));
+    }
+}
+
+#[test]
+pub fn test_float_canonical_nan() {
+    let config = Config::new().with_canonical_nan(true);
+    let nan1 = f32::from_bits(0x7fa0_0001);
+    let nan2 = f32::from_bits(0xffc0_1234);
+    assert_eq!(vec![0, 0, 192, 127], nan1.to_data_with(config));
+    assert_eq!(nan1.to_data_with(config), nan2.to_data_with(config));
+    assert_ne!(nan1.to_data(), nan2.to_data());
+    assert_eq!((-0.0_f32).to_data(), (-0.0_f32).to_data_with(config));
+
+    let nan = f64::from_bits(0xfff8_dead_beef_0000);
+    assert_eq!(vec![0, 0, 0, 0, 0, 0, 248, 127], nan.to_data_with(config));
+    let value = vec![1.0, f64::NAN, -f64::NAN];
+    let data = value.to_data_with(config);
+    let (res, _) = Vec::<f64>::try_from_data_with(&data, 0, config).unwrap();
+    assert_eq!(1.0, res[0]);
+    assert_eq!(res[1].to_bits(), res[2].to_bits());
+}
+
+#[test]
+pub fn test_duration() {
+    let value = Duration::new(3, 5);
+    let data = value.to_data();
+    assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0], data);
+    assert_eq!((value, 12), Duration::from_data(&data, 0));
+
+    for value in [Duration::ZERO, Duration::new(0, 999_999_999), Duration::MAX] {
+        assert_eq!((value, 12), Duration::from_data(&value.to_data(), 0));
+        let data = value.to_data_with(VARINT);
+        assert_eq!(
+            Ok((value, data.len())),
+            Duration::try_from_data_with(&data, 0, VARINT)
+        );
+    }
+}
+
+#[test]
+pub fn test_duration_invalid_nanos() {
+    let mut data = 3_u64.to_data();
+    data.extend(1_000_000_000_u32.to_data());
+    assert_eq!(
+        Err(DeserializeError::OutOfRange {
+            type_name: "Duration"
+        }),
+        Duration::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_system_time() {
+    let nano = Duration::from_nanos(1);
+    let second = Duration::from_secs(1);
+    for (value, secs, nanos) in [
+        (UNIX_EPOCH, 0_i64, 0_u32),
+        (UNIX_EPOCH + nano, 0, 1),
+        (UNIX_EPOCH - nano, -1, 999_999_999),
+        (UNIX_EPOCH - second, -1, 0),
+        (UNIX_EPOCH - second - nano, -2, 999_999_999),
+        (UNIX_EPOCH + second - nano, 0, 999_999_999),
+        // 1969-07-20 20:17:40 UTC
+        (UNIX_EPOCH - Duration::new(14_182_940, 0), -14_182_940, 0),
+    ] {
+        let data = value.to_data();
+        assert_eq!((secs, nanos).to_data(), data);
+        assert_eq!((value, 12), SystemTime::from_data(&data, 0));
+    }
+
+    let now = SystemTime::now();
+    assert_eq!((now, 12), SystemTime::from_data(&now.to_data(), 0));
+    let data = now.to_data_with(BIG_ENDIAN);
+    assert_eq!(
+        Ok((now, 12)),
+        SystemTime::try_from_data_with(&data, 0, BIG_ENDIAN)
+    );
+}
+
+#[test]
+pub fn test_system_time_invalid() {
+    let data = (-1_i64, 1_000_000_000_u32).to_data();
+    assert_eq!(
+        Err(DeserializeError::OutOfRange {
+            type_name: "SystemTime"
+        }),
+        SystemTime::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_atomic() {
+    let value = AtomicU64::new(555);
+    let data = value.to_data();
+    assert_eq!(555_u64.to_data(), data);
+    let (res, index) = AtomicU64::from_data(&data, 0);
+    assert_eq!((555, 8), (res.into_inner(), index));
+
+    let value = AtomicBool::new(true);
+    assert_eq!(vec![1], value.to_data());
+    assert!(AtomicBool::from_data(&[1], 0).0.into_inner());
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "bool",
+            tag: 2,
+        }),
+        AtomicBool::try_from_data(&[2], 0).map(|(v, i)| (v.into_inner(), i))
+    );
+
+    let value = AtomicI16::new(-2);
+    let data = value.to_data_with(VARINT);
+    assert_eq!(vec![3], data);
+    let (res, _) = AtomicI16::try_from_data_with(&data, 0, VARINT).unwrap();
+    assert_eq!(-2, res.into_inner());
+
+    let value = (AtomicUsize::new(3), AtomicIsize::new(-3));
+    let data = value.to_data();
+    let ((a, b), index) = <(AtomicUsize, AtomicIsize)>::from_data(&data, 0);
+    assert_eq!((3, -3, 16), (a.into_inner(), b.into_inner(), index));
+}
+
+#[test]
+pub fn test_atomic_load_ordering() {
+    assert_eq!(atomic::Ordering::SeqCst, Config::new().load_ordering());
+    for (ordering, load) in [
+        (atomic::Ordering::Relaxed, atomic::Ordering::Relaxed),
+        (atomic::Ordering::Acquire, atomic::Ordering::Acquire),
+        (atomic::Ordering::Release, atomic::Ordering::Relaxed),
+        (atomic::Ordering::AcqRel, atomic::Ordering::Acquire),
+        (atomic::Ordering::SeqCst, atomic::Ordering::SeqCst),
+    ] {
+        let config = Config::new().with_load_ordering(ordering);
+        assert_eq!(load, config.load_ordering());
+        assert_eq!(
+            vec![7, 0, 0, 0, 0, 0, 0, 0],
+            AtomicU64::new(7).to_data_with(config)
+        );
+    }
+}
+
 #[test]
 pub fn test_bool() {
     let value = true;
@@ -115,6 +412,47 @@ pub fn test_bool() {
     assert_eq!((value, 1), bool::from_data(&data, 0));
 }
 
+#[test]
+pub fn test_char() {
+    for (value, expected) in [
+        ('a', vec![0x61]),
+        ('é', vec![0xc3, 0xa9]),
+        ('€', vec![0xe2, 0x82, 0xac]),
+        ('日', vec![0xe6, 0x97, 0xa5]),
+        ('🦀', vec![0xf0, 0x9f, 0xa6, 0x80]),
+        (char::MAX, vec![0xf4, 0x8f, 0xbf, 0xbf]),
+    ] {
+        let data = value.to_data();
+        assert_eq!(expected, data);
+        assert_eq!((value, expected.len()), char::from_data(&data, 0));
+    }
+    let value = vec!['€', 'a', '日'];
+    let data = value.to_data();
+    assert_eq!(8 + 7, data.len());
+    assert_eq!((value, 15), Vec::from_data(&data, 0));
+}
+
+#[test]
+pub fn test_char_invalid() {
+    let invalid = Err(DeserializeError::InvalidUtf8 { type_name: "char" });
+    // Surrogate U+D800.
+    assert_eq!(invalid, char::try_from_data(&[0xed, 0xa0, 0x80], 0));
+    // Out of the Unicode range U+110000.
+    assert_eq!(invalid, char::try_from_data(&[0xf4, 0x90, 0x80, 0x80], 0));
+    // Continuation byte first, overlong form.
+    assert_eq!(invalid, char::try_from_data(&[0x80], 0));
+    assert_eq!(invalid, char::try_from_data(&[0xc0, 0xaf], 0));
+    assert_eq!(invalid, char::try_from_data(&[0xff], 0));
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "char",
+            needed: 3,
+            available: 2,
+        }),
+        char::try_from_data(&[0xe2, 0x82], 0)
+    );
+}
+
 #[test]
 pub fn test_string() {
     let value = String::from("This is a test value");
@@ -129,6 +467,70 @@ pub fn test_string() {
     assert_eq!((value, 28), String::from_data(&data, 0));
 }
 
+#[test]
+pub fn test_path() {
+    let value = PathBuf::from("/tmp/cache/a.bin");
+    let data = value.to_data();
+    assert_eq!(String::from("/tmp/cache/a.bin").to_data(), data);
+    assert_eq!(data, Path::new("/tmp/cache/a.bin").to_data());
+    assert_eq!((value, 24), PathBuf::from_data(&data, 0));
+
+    let value = OsString::from("cache");
+    let data = value.to_data();
+    assert_eq!(data, OsStr::new("cache").to_data());
+    assert_eq!((value.clone(), 13), OsString::from_data(&data, 0));
+
+    let data = value.to_data_with(VARINT);
+    assert_eq!(vec![5, 99, 97, 99, 104, 101], data);
+    assert_eq!(
+        Ok((value, 6)),
+        OsString::try_from_data_with(&data, 0, VARINT)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+pub fn test_path_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let value = PathBuf::from(OsStr::from_bytes(b"/tmp/\xff\xfe.bin"));
+    let data = value.to_data();
+    assert_eq!(
+        vec![11, 0, 0, 0, 0, 0, 0, 0, 47, 116, 109, 112, 47, 255, 254, 46, 98, 105, 110],
+        data
+    );
+    assert_eq!((value, 19), PathBuf::from_data(&data, 0));
+    assert_eq!(
+        Err(DeserializeError::InvalidUtf8 {
+            type_name: "String"
+        }),
+        String::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_cstring() {
+    let value = CString::new("hello").unwrap();
+    let data = value.to_data();
+    assert_eq!(vec![5, 0, 0, 0, 0, 0, 0, 0, 104, 101, 108, 108, 111], data);
+    assert_eq!(data, c"hello".to_data());
+    assert_eq!((value.clone(), 13), CString::from_data(&data, 0));
+    let data = value.to_data_with(BIG_ENDIAN);
+    assert_eq!(
+        Ok((value, 13)),
+        CString::try_from_data_with(&data, 0, BIG_ENDIAN)
+    );
+
+    let data = vec![3, 0, 0, 0, 0, 0, 0, 0, 104, 0, 105];
+    assert_eq!(
+        Err(DeserializeError::InteriorNul {
+            type_name: "CString",
+            position: 1,
+        }),
+        CString::try_from_data(&data, 0)
+    );
+}
+
 #[test]
 pub fn test_option() {
     let value = Some(String::from("This is optional"));
@@ -143,6 +545,234 @@ pub fn test_option() {
     assert_eq!((value, 25), Option::from_data(&data, 0));
 }
 
+#[test]
+pub fn test_result() {
+    let value: Result<u16, String> = Ok(555);
+    let data = value.to_data();
+    assert_eq!(vec![0, 43, 2], data);
+    assert_eq!((value, 3), Result::from_data(&data, 0));
+
+    let value: Result<u16, String> = Err(String::from("ko"));
+    let data = value.to_data();
+    assert_eq!(vec![1, 2, 0, 0, 0, 0, 0, 0, 0, 107, 111], data);
+    assert_eq!((value, 11), Result::from_data(&data, 0));
+}
+
+#[test]
+pub fn test_unit_and_phantom() {
+    assert!(().to_data().is_empty());
+    assert_eq!(((), 3), <()>::from_data(&[1, 2, 3], 3));
+    assert!(PhantomData::<String>.to_data().is_empty());
+    assert_eq!((PhantomData::<String>, 0), PhantomData::from_data(&[], 0));
+
+    let value = (1_u8, (), PhantomData::<str>, 2_u8);
+    let data = value.to_data();
+    assert_eq!(vec![1, 2], data);
+    assert_eq!(
+        (value, 2),
+        <(u8, (), PhantomData<str>, u8)>::from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_empty_element() {
+    let mut out = Vec::new();
+    let err = vec![(), ()].serialize_into(&mut out).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    assert_eq!(0_usize.to_data(), Vec::<()>::new().to_data());
+
+    assert_eq!(
+        Err(DeserializeError::EmptyElement { type_name: "Vec" }),
+        Vec::<()>::try_from_data(&[1, 0, 0, 0, 0, 0, 0, 0, 0], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::EmptyElement {
+            type_name: "HashMap"
+        }),
+        HashMap::<(), PhantomData<u8>>::try_from_data(&[1, 0, 0, 0, 0, 0, 0, 0, 0], 0)
+    );
+}
+
+#[test]
+pub fn test_try_to_data() {
+    let err = vec![(), ()].try_to_data().unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    let err = std::rc::Weak::<u8>::new().try_to_data().unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    let cell = RefCell::new(5_u8);
+    let _borrow = cell.borrow_mut();
+    assert!(cell.try_to_data_with(VARINT).is_err());
+    assert_eq!(Ok(vec![5]), 5_u8.try_to_data().map_err(|e| e.kind()));
+}
+
+#[test]
+#[should_panic(expected = "Error when serialize")]
+pub fn test_to_data_panic() {
+    vec![(), ()].to_data();
+}
+
+#[test]
+pub fn test_range() {
+    let value = 3_u64..7;
+    let data = value.to_data();
+    assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0], data);
+    assert_eq!((value, 16), Range::from_data(&data, 0));
+
+    let value = 3_u16..=7;
+    let data = value.to_data_with(BIG_ENDIAN);
+    assert_eq!(vec![0, 3, 0, 7], data);
+    assert_eq!(
+        Ok((value, 4)),
+        RangeInclusive::try_from_data_with(&data, 0, BIG_ENDIAN)
+    );
+
+    let value = 3_u8..;
+    assert_eq!(vec![3], value.to_data());
+    assert_eq!((value, 1), RangeFrom::from_data(&[3], 0));
+
+    let value = ..7_u8;
+    assert_eq!(vec![7], value.to_data());
+    assert_eq!((value, 1), RangeTo::from_data(&[7], 0));
+
+    let value = ..=7_u8;
+    assert_eq!(vec![7], value.to_data());
+    assert_eq!((value, 1), RangeToInclusive::from_data(&[7], 0));
+
+    assert!((..).to_data().is_empty());
+    assert_eq!((.., 0), RangeFull::from_data(&[], 0));
+}
+
+#[test]
+pub fn test_bound() {
+    let value = (Bound::Included(3_u8), Bound::Excluded(7_u8));
+    let data = value.to_data();
+    assert_eq!(vec![0, 3, 1, 7], data);
+    assert_eq!((value, 4), <(Bound<u8>, Bound<u8>)>::from_data(&data, 0));
+
+    let value = (Bound::Unbounded, Bound::Included(String::from("z")));
+    let data = value.to_data();
+    assert_eq!(vec![2, 0, 1, 0, 0, 0, 0, 0, 0, 0, 122], data);
+    assert_eq!(
+        (value, 11),
+        <(Bound<String>, Bound<String>)>::from_data(&data, 0)
+    );
+
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Bound",
+            tag: 3,
+        }),
+        Bound::<u8>::try_from_data(&[3, 1], 0)
+    );
+}
+
+#[test]
+pub fn test_ordering() {
+    for (value, code) in [
+        (Ordering::Less, 0),
+        (Ordering::Equal, 1),
+        (Ordering::Greater, 2),
+    ] {
+        assert_eq!(vec![code], value.to_data());
+        assert_eq!((value, 1), Ordering::from_data(&[code], 0));
+    }
+}
+
+#[test]
+pub fn test_num_wrappers() {
+    let value = Reverse(555_u16);
+    let data = value.to_data();
+    assert_eq!(vec![43, 2], data);
+    assert_eq!((value, 2), Reverse::from_data(&data, 0));
+
+    let value = Wrapping(-2_i32);
+    let data = value.to_data_with(VARINT);
+    assert_eq!(vec![3], data);
+    assert_eq!(
+        Ok((value, 1)),
+        Wrapping::try_from_data_with(&data, 0, VARINT)
+    );
+
+    let value = Saturating(555_u16);
+    let data = value.to_data_with(BIG_ENDIAN);
+    assert_eq!(vec![2, 43], data);
+    assert_eq!(
+        Ok((value, 2)),
+        Saturating::try_from_data_with(&data, 0, BIG_ENDIAN)
+    );
+}
+
+#[test]
+pub fn test_tuple() {
+    let value = (2034_u32, String::from("ab"));
+    let data = value.to_data();
+    assert_eq!(vec![242, 7, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 97, 98], data);
+    assert_eq!((value, 14), <(u32, String)>::from_data(&data, 0));
+
+    let value = (1_u8,);
+    assert_eq!(vec![1], value.to_data());
+    assert_eq!((value, 1), <(u8,)>::from_data(&[1], 0));
+
+    let value = (
+        1_u8,
+        2_u16,
+        3_u32,
+        4_u64,
+        5_u128,
+        6_i8,
+        7_i16,
+        8_i32,
+        9_i64,
+        10_i128,
+        true,
+        'c',
+        String::from("s"),
+        Some(14_u8),
+        vec![15_u8],
+        (16_u8, 17_u8),
+    );
+    let data = value.to_data();
+    // Debug and PartialEq are only implemented up to 12 elements, compare the data.
+    fn decode<T: DeserializerData>(_: &T, data: &[u8]) -> (T, usize) {
+        T::from_data(data, 0)
+    }
+    let (res, index) = decode(&value, &data);
+    assert_eq!(data.len(), index);
+    assert_eq!(data, res.to_data());
+    assert_eq!(String::from("s"), res.12);
+    assert_eq!((16, 17), res.15);
+}
+
+#[test]
+pub fn test_array() {
+    let value = [7_u8; 32];
+    let data = value.to_data();
+    assert_eq!(vec![7; 32], data);
+    assert_eq!((value, 32), <[u8; 32]>::from_data(&data, 0));
+
+    // No Default or Copy needed.
+    let value = [String::from("a"), String::from("bc")];
+    let data = value.to_data();
+    assert_eq!(
+        vec![1, 0, 0, 0, 0, 0, 0, 0, 97, 2, 0, 0, 0, 0, 0, 0, 0, 98, 99],
+        data
+    );
+    assert_eq!((value, 19), <[String; 2]>::from_data(&data, 0));
+
+    let value: [u32; 0] = [];
+    assert!(value.to_data().is_empty());
+    assert_eq!((value, 0), <[u32; 0]>::from_data(&[], 0));
+
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u16",
+            needed: 2,
+            available: 1,
+        }),
+        <[u16; 3]>::try_from_data(&[1, 0, 2, 0, 3], 0)
+    );
+}
+
 #[test]
 pub fn test_vec() {
     let value = vec![String::from("Value 1"), String::from("Value 2")];
@@ -269,9 +899,868 @@ pub fn test_btreemap() {
 pub fn test_socketaddr() {
     let value: SocketAddr = "127.0.0.1:3000".parse().unwrap();
     let data = value.to_data();
+    assert_eq!(vec![0, 127, 0, 0, 1, 184, 11], data);
+    assert_eq!((value, 7), SocketAddr::from_data(&data, 0));
+
+    let value = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 3000, 5, 7));
+    let data = value.to_data();
+    assert_eq!(
+        vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 184, 11, 5, 0, 0, 0, 7, 0, 0, 0],
+        data
+    );
+    assert_eq!((value, 27), SocketAddr::from_data(&data, 0));
+
+    let data = value.to_data_with(BIG_ENDIAN);
+    assert_eq!(vec![11, 184, 0, 0, 0, 5, 0, 0, 0, 7], data[17..]);
+    assert_eq!(
+        Ok((value, 27)),
+        SocketAddr::try_from_data_with(&data, 0, BIG_ENDIAN)
+    );
+}
+
+#[test]
+pub fn test_ip_addr() {
+    let value = Ipv4Addr::new(192, 168, 1, 10);
+    assert_eq!(vec![192, 168, 1, 10], value.to_data());
+    assert_eq!((value, 4), Ipv4Addr::from_data(&[192, 168, 1, 10], 0));
+
+    let value: Ipv6Addr = "2001:db8::1".parse().unwrap();
+    let data = value.to_data();
+    assert_eq!(value.octets().to_vec(), data);
+    assert_eq!((value, 16), Ipv6Addr::from_data(&data, 0));
+
+    let value = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let data = value.to_data();
+    assert_eq!(vec![0, 127, 0, 0, 1], data);
+    assert_eq!((value, 5), IpAddr::from_data(&data, 0));
+
+    let value = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+    let data = value.to_data();
+    assert_eq!(17, data.len());
+    assert_eq!(1, data[0]);
+    assert_eq!((value, 17), IpAddr::from_data(&data, 0));
+
+    let value = SocketAddrV4::new(Ipv4Addr::BROADCAST, 80);
+    let data = value.to_data_with(VARINT);
+    assert_eq!(vec![255, 255, 255, 255, 80], data);
+    assert_eq!(
+        Ok((value, 5)),
+        SocketAddrV4::try_from_data_with(&data, 0, VARINT)
+    );
+}
+
+#[test]
+pub fn test_legacy_socketaddr() {
+    let addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+    let data = LegacySocketAddr(addr).to_data();
     assert_eq!(
         vec![14, 0, 0, 0, 0, 0, 0, 0, 49, 50, 55, 46, 48, 46, 48, 46, 49, 58, 51, 48, 48, 48],
         data
     );
-    assert_eq!((value, 22), SocketAddr::from_data(&data, 0));
+    assert_eq!(
+        (LegacySocketAddr(addr), 22),
+        LegacySocketAddr::from_data(&data, 0)
+    );
+
+    let addr: SocketAddr = "[::1%3]:8080".parse().unwrap();
+    let data = addr.to_string().to_data();
+    assert_eq!(
+        Ok((LegacySocketAddr(addr), data.len())),
+        LegacySocketAddr::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_truncated() {
+    let data = vec![73, 197];
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u32",
+            needed: 4,
+            available: 2,
+        }),
+        u32::try_from_data(&data, 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u8",
+            needed: 1,
+            available: 0,
+        }),
+        u8::try_from_data(&data, 5)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error when deserialize u32")]
+pub fn test_truncated_panic() {
+    u32::from_data(&[73, 197], 0);
+}
+
+#[test]
+pub fn test_string_invalid_utf8() {
+    let data = vec![2, 0, 0, 0, 0, 0, 0, 0, 0xc3, 0x28];
+    assert_eq!(
+        Err(DeserializeError::InvalidUtf8 {
+            type_name: "String"
+        }),
+        String::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_string_size_overflow() {
+    let data = vec![255, 255, 255, 255, 255, 255, 255, 255, 84];
+    assert!(matches!(
+        String::try_from_data(&data, 0),
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "String",
+            available: 1,
+            ..
+        })
+    ));
+}
+
+#[test]
+pub fn test_invalid_tag() {
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "bool",
+            tag: 2,
+        }),
+        bool::try_from_data(&[2], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Option",
+            tag: 7,
+        }),
+        Option::<u8>::try_from_data(&[7, 1], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Result",
+            tag: 2,
+        }),
+        Result::<u8, u8>::try_from_data(&[2, 1], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "Ordering",
+            tag: 3,
+        }),
+        Ordering::try_from_data(&[3], 0)
+    );
+}
+
+#[test]
+pub fn test_socketaddr_invalid() {
+    let data = String::from("localhost").to_data();
+    assert_eq!(
+        Err(DeserializeError::InvalidSocketAddr {
+            value: String::from("localhost")
+        }),
+        LegacySocketAddr::try_from_data(&data, 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "SocketAddr",
+            tag: 2,
+        }),
+        SocketAddr::try_from_data(&[2, 127, 0, 0, 1, 184, 11], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::InvalidTag {
+            type_name: "IpAddr",
+            tag: 4,
+        }),
+        IpAddr::try_from_data(&[4, 127, 0, 0, 1], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "Ipv6Addr",
+            needed: 16,
+            available: 4,
+        }),
+        SocketAddr::try_from_data(&[1, 0, 0, 0, 0], 0)
+    );
+}
+
+#[test]
+pub fn test_vec_truncated_element() {
+    let data = vec![3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0];
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u16",
+            needed: 2,
+            available: 1,
+        }),
+        Vec::<u16>::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_serialize_into() {
+    let value = vec![String::from("Value 1"), String::from("Value 2")];
+    let mut data = vec![9];
+    value.serialize_into(&mut data).unwrap();
+    let mut expected = vec![9];
+    expected.append(&mut value.to_data());
+    assert_eq!(expected, data);
+}
+
+#[test]
+pub fn test_serialize_into_writer() {
+    let mut buffer = [0u8; 10];
+    let mut out: &mut [u8] = &mut buffer;
+    out.write_bytes(&[1]).unwrap();
+    2034_u16.serialize_into(&mut out).unwrap();
+    assert_eq!(7, out.len());
+    assert_eq!([1, 242, 7, 0], buffer[..4]);
+
+    let mut small = [0u8; 4];
+    let mut out: &mut [u8] = &mut small;
+    let err = String::from("Value").serialize_into(&mut out).unwrap_err();
+    assert_eq!(io::ErrorKind::WriteZero, err.kind());
+}
+
+#[test]
+pub fn test_vec_large() {
+    let value = (0..2_000_000_u32).collect::<Vec<u32>>();
+    let data = value.to_data();
+    assert_eq!(8 + 8_000_000, data.len());
+    assert_eq!((value, 8_000_008), Vec::from_data(&data, 0));
+}
+
+#[test]
+pub fn test_vec_bytes_large() {
+    let value = (0..16_000_000_u32).map(|v| v as u8).collect::<Vec<u8>>();
+    let data = value.to_data();
+    assert_eq!(8 + 16_000_000, data.len());
+    assert_eq!(data, value.as_slice().to_data());
+    let (res, index) = Vec::<u8>::from_data(&data, 0);
+    assert_eq!(8 + 16_000_000, index);
+    assert!(value == res);
+}
+
+/// The primitives read and write all the elements at once, the bytes are the same as element by element.
+fn assert_bulk<T>(value: Vec<T>)
+where
+    T: SerializerData + DeserializerData + Clone + std::fmt::Debug + PartialEq,
+{
+    for config in [Config::new(), BIG_ENDIAN, VARINT, COUNT] {
+        let data = value.to_data_with(config);
+        let deque = value.iter().cloned().collect::<VecDeque<T>>();
+        assert_eq!(deque.to_data_with(config), data);
+        assert_eq!(
+            Ok((value.clone(), data.len())),
+            Vec::try_from_data_with(&data, 0, config)
+        );
+        assert_eq!(
+            Ok((deque, data.len())),
+            VecDeque::try_from_data_with(&data, 0, config)
+        );
+    }
+}
+
+#[test]
+pub fn test_vec_bulk() {
+    assert_bulk(vec![0_u8, 1, 255]);
+    assert_bulk(vec![0_i8, -1, 127, -128]);
+    assert_bulk(vec![0_u16, 1, 555, u16::MAX]);
+    assert_bulk(vec![0_u32, 1, 555, u32::MAX]);
+    assert_bulk(vec![0_u64, 1, 555, u64::MAX]);
+    assert_bulk(vec![0_u128, 1, 555, u128::MAX]);
+    assert_bulk(vec![0_i16, -1, 555, i16::MIN]);
+    assert_bulk(vec![0_i32, -1, 555, i32::MIN]);
+    assert_bulk(vec![0_i64, -1, 555, i64::MIN]);
+    assert_bulk(vec![0_i128, -1, 555, i128::MIN]);
+    assert_bulk(vec![0_f32, -1.5, f32::INFINITY, f32::MIN_POSITIVE]);
+    assert_bulk(vec![0_f64, -1.5, f64::NEG_INFINITY, f64::EPSILON]);
+    assert_bulk(Vec::<u32>::new());
+
+    let config = Config::new().with_canonical_nan(true);
+    let value = vec![f64::NAN, -f64::NAN, 1.0];
+    let data = value.to_data_with(config);
+    let deque = value.iter().copied().collect::<VecDeque<f64>>();
+    assert_eq!(deque.to_data_with(config), data);
+    assert_eq!(f64::NAN.to_data_with(config), data[8..16]);
+    assert_eq!(data[8..16], data[16..24]);
+}
+
+#[test]
+pub fn test_vec_bulk_truncated() {
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "Vec",
+            needed: 4,
+            available: 3,
+        }),
+        Vec::<u8>::try_from_data(&[4, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3], 0)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "f64",
+            needed: 8,
+            available: 4,
+        }),
+        Vec::<f64>::try_from_data(
+            &[12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            0
+        )
+    );
+}
+
+#[test]
+pub fn test_vec_element_overrun() {
+    // The size only covers one byte of the second u16.
+    let data = vec![3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0];
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u16",
+            needed: 2,
+            available: 1,
+        }),
+        Vec::<u16>::try_from_data(&data, 0)
+    );
+}
+
+#[test]
+pub fn test_count_prefix() {
+    let value = vec![1_u16, 2, 3];
+    let data = value.to_data_with(COUNT);
+    assert_eq!(vec![3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0, 3, 0], data);
+    assert_eq!(Ok((value, 14)), Vec::try_from_data_with(&data, 0, COUNT));
+
+    let value = vec![vec![String::from("a")], vec![], vec![String::from("bc")]];
+    let data = value.to_data_with(COUNT);
+    assert_eq!(3, usize::from_data(&data, 0).0);
+    assert_eq!(1, usize::from_data(&data, 8).0);
+    assert_eq!(
+        Ok((value, data.len())),
+        Vec::try_from_data_with(&data, 0, COUNT)
+    );
+
+    let value = HashMap::from([(1_u8, String::from("one")), (2, String::from("two"))]);
+    let data = value.to_data_with(COUNT);
+    assert_eq!(2, usize::from_data(&data, 0).0);
+    assert_eq!(
+        Ok((value, data.len())),
+        HashMap::try_from_data_with(&data, 0, COUNT)
+    );
+
+    let value = BTreeSet::from([3_i64, -1, 7]);
+    let data = value.to_data_with(COUNT.with_int_encoding(IntEncoding::Varint));
+    assert_eq!(vec![3, 1, 6, 14], data);
+    assert_eq!(
+        Ok((value, 4)),
+        BTreeSet::try_from_data_with(&data, 0, COUNT.with_int_encoding(IntEncoding::Varint))
+    );
+}
+
+#[test]
+pub fn test_count_empty_element() {
+    let value = vec![(), (), ()];
+    let data = value.to_data_with(COUNT);
+    assert_eq!(3_usize.to_data(), data);
+    assert_eq!(Ok((value, 8)), Vec::try_from_data_with(&data, 0, COUNT));
+    assert_eq!(
+        Ok((HashSet::from([()]), 8)),
+        HashSet::try_from_data_with(&1_usize.to_data(), 0, COUNT)
+    );
+
+    // More elements without data than the reader accepts can not be written.
+    let limit = 1_usize << 20;
+    let err = vec![(); limit + 1].try_to_data_with(COUNT).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    let err = VecDeque::from(vec![PhantomData::<u8>; limit + 1])
+        .try_to_data_with(COUNT)
+        .unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    let value = vec![Some(()); limit + 1];
+    let data = value.to_data_with(COUNT);
+    assert_eq!((limit + 1, 8), usize::from_data(&data, 0));
+    assert_eq!(
+        Ok((value, data.len())),
+        Vec::try_from_data_with(&data, 0, COUNT)
+    );
+
+    // An invalid count of elements without data is rejected, not read almost forever.
+    let data = vec![(); limit].to_data_with(COUNT);
+    assert_eq!(
+        Ok(limit),
+        Vec::<()>::try_from_data_with(&data, 0, COUNT).map(|(v, _)| v.len())
+    );
+    assert_eq!(
+        Err(DeserializeError::EmptyElement { type_name: "Vec" }),
+        Vec::<()>::try_from_data_with(&(limit + 1).to_data(), 0, COUNT)
+    );
+    assert_eq!(
+        Err(DeserializeError::EmptyElement { type_name: "Vec" }),
+        Vec::<PhantomData<u8>>::try_from_data_with(&u64::MAX.to_data(), 0, COUNT)
+    );
+    assert_eq!(
+        Err(DeserializeError::EmptyElement {
+            type_name: "HashMap"
+        }),
+        HashMap::<(), RangeFull>::try_from_data_with(&(1_usize << 34).to_data(), 0, COUNT)
+    );
+}
+
+#[test]
+pub fn test_count_truncated() {
+    // The count is far larger than the data, the preallocation is capped.
+    let mut data = usize::MAX.to_data();
+    data.extend([1, 0, 0, 0]);
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u64",
+            needed: 8,
+            available: 4,
+        }),
+        Vec::<String>::try_from_data_with(&data, 0, COUNT)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u8",
+            needed: 1,
+            available: 0,
+        }),
+        HashMap::<u8, u8>::try_from_data_with(&data[..8], 0, COUNT)
+    );
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "Vec",
+            needed: usize::MAX,
+            available: 4,
+        }),
+        Vec::<u32>::try_from_data_with(&data, 0, COUNT)
+    );
+}
+
+#[test]
+pub fn test_count_migration() {
+    let value = vec![
+        BTreeMap::from([(String::from("a"), vec![1_u32, 2])]),
+        BTreeMap::new(),
+    ];
+    let old = value.to_data();
+    let (res, _) =
+        Vec::<BTreeMap<String, Vec<u32>>>::try_from_data_with(&old, 0, Config::new()).unwrap();
+    let new = res.to_data_with(COUNT);
+    assert_ne!(old, new);
+    assert_eq!(
+        Ok((value, new.len())),
+        Vec::try_from_data_with(&new, 0, COUNT)
+    );
+}
+
+/// Maps with the same content, each with a hasher seeded differently.
+fn seeded_maps() -> Vec<HashMap<String, u32>> {
+    (0..10)
+        .map(|_| {
+            let mut map = HashMap::with_hasher(RandomState::new());
+            map.extend((0..64).map(|i| (format!("key{i}"), i)));
+            map
+        })
+        .collect()
+}
+
+#[test]
+pub fn test_sorted_keys() {
+    let maps = seeded_maps();
+    let unsorted = maps.iter().map(|m| m.to_data()).collect::<HashSet<_>>();
+    assert!(unsorted.len() > 1);
+
+    for config in [Config::new(), VARINT, COUNT] {
+        let config = config.with_sorted_keys(true);
+        let data = maps[0].to_data_with(config);
+        for map in &maps[1..] {
+            assert_eq!(data, map.to_data_with(config));
+        }
+        assert_eq!(
+            Ok((maps[0].clone(), data.len())),
+            HashMap::try_from_data_with(&data, 0, config)
+        );
+
+        let sets = maps
+            .iter()
+            .map(|m| m.keys().cloned().collect::<HashSet<_>>())
+            .collect::<Vec<_>>();
+        let data = sets[0].to_data_with(config);
+        for set in &sets[1..] {
+            assert_eq!(data, set.to_data_with(config));
+        }
+    }
+
+    // Sorted by the encoded bytes, the size of the String first.
+    let set = HashSet::from([String::from("b"), String::from("aa")]);
+    let data = set.to_data_with(Config::new().with_sorted_keys(true));
+    assert_eq!(vec![String::from("b"), String::from("aa")].to_data(), data);
+}
+
+#[test]
+pub fn test_sorted_ord() {
+    let maps = seeded_maps();
+    let tree = maps[0].clone().into_iter().collect::<BTreeMap<_, _>>();
+    for map in &maps {
+        assert_eq!(tree.to_data(), Sorted(map).to_data());
+    }
+    let set = maps[0].keys().cloned().collect::<HashSet<_>>();
+    let data = Sorted(&set).to_data_with(COUNT);
+    assert_eq!(
+        set.iter()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .to_data_with(COUNT),
+        data
+    );
+    assert_eq!(
+        Ok((set, data.len())),
+        HashSet::try_from_data_with(&data, 0, COUNT)
+    );
+}
+
+/// FNV-1a hasher, a hasher without random state like the ones of FxHashMap or AHashMap.
+#[derive(Default)]
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+type FnvBuildHasher = BuildHasherDefault<FnvHasher>;
+
+#[test]
+pub fn test_custom_hasher() {
+    let mut map = HashMap::<u32, String, FnvBuildHasher>::default();
+    map.extend((0..100).map(|i| (i, i.to_string())));
+    let config = Config::new().with_sorted_keys(true);
+    let data = map.to_data_with(config);
+    let std_map = map.clone().into_iter().collect::<HashMap<_, _>>();
+    assert_eq!(std_map.to_data_with(config), data);
+    assert_eq!(
+        Ok((map.clone(), data.len())),
+        HashMap::try_from_data_with(&data, 0, config)
+    );
+
+    let set = map.into_keys().collect::<HashSet<u32, FnvBuildHasher>>();
+    let data = set.to_data_with(COUNT);
+    let (res, index) = HashSet::<u32, FnvBuildHasher>::try_from_data_with(&data, 0, COUNT).unwrap();
+    assert_eq!(data.len(), index);
+    assert!(res.capacity() >= 100);
+    assert_eq!(set, res);
+    assert_eq!(
+        set.iter().copied().collect::<BTreeSet<_>>().to_data(),
+        Sorted(&set).to_data()
+    );
+}
+
+#[test]
+pub fn test_little_endian() {
+    let config = Config::new().with_byte_order(ByteOrder::LittleEndian);
+    assert_eq!(Config::default(), config);
+    assert_eq!(
+        vec![0x04, 0x03, 0x02, 0x01],
+        0x01020304_u32.to_data_with(config)
+    );
+    assert_eq!(vec![0x04, 0x03, 0x02, 0x01], 0x01020304_u32.to_data());
+    assert_eq!(
+        vec![0xf8, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        (-8_i64).to_data()
+    );
+}
+
+#[test]
+pub fn test_big_endian() {
+    assert_eq!(vec![234], 234_u8.to_data_with(BIG_ENDIAN));
+    assert_eq!(vec![2, 43], 555_u16.to_data_with(BIG_ENDIAN));
+    assert_eq!(vec![0, 0, 197, 73], 50505_u32.to_data_with(BIG_ENDIAN));
+    assert_eq!(
+        vec![0, 0, 0, 0, 0, 14, 247, 29],
+        980765_u64.to_data_with(BIG_ENDIAN)
+    );
+    assert_eq!(
+        vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 73, 150, 2, 210],
+        1234567890_u128.to_data_with(BIG_ENDIAN)
+    );
+    assert_eq!(vec![255, 254], (-2_i16).to_data_with(BIG_ENDIAN));
+    assert_eq!(
+        vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf8],
+        (-8_i64).to_data_with(BIG_ENDIAN)
+    );
+
+    assert_eq!(
+        Ok((555, 2)),
+        u16::try_from_data_with(&[2, 43], 0, BIG_ENDIAN)
+    );
+    assert_eq!(
+        Ok((50505, 4)),
+        u32::try_from_data_with(&[0, 0, 197, 73], 0, BIG_ENDIAN)
+    );
+    assert_eq!(
+        Ok((-2, 2)),
+        i16::try_from_data_with(&[255, 254], 0, BIG_ENDIAN)
+    );
+}
+
+#[test]
+pub fn test_big_endian_nested() {
+    let mut value = BTreeMap::new();
+    value.insert(String::from("KEY1"), vec![255_u32, 896]);
+    let data = value.to_data_with(BIG_ENDIAN);
+    assert_eq!(
+        vec![
+            0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0, 0, 0, 0, 4, 75, 69, 89, 49, 0, 0, 0, 0, 0, 0, 0,
+            8, 0, 0, 0, 255, 0, 0, 3, 128
+        ],
+        data
+    );
+    assert_eq!(
+        Ok((value.clone(), 36)),
+        BTreeMap::try_from_data_with(&data, 0, BIG_ENDIAN)
+    );
+    assert_ne!(Ok((value, 36)), BTreeMap::try_from_data(&data, 0));
+}
+
+#[test]
+pub fn test_config_writer_reader() {
+    let mut out = ConfigWriter::new(Vec::new(), BIG_ENDIAN);
+    assert_eq!(BIG_ENDIAN, out.config());
+    String::from("ab").serialize_into(&mut out).unwrap();
+    2034_i32.serialize_into(&mut out).unwrap();
+    let data = out.into_inner();
+    assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 2, 97, 98, 0, 0, 7, 242], data);
+
+    let reader = ConfigReader::new(&data, BIG_ENDIAN);
+    let (value, index) = String::from_data(&reader, 0);
+    assert_eq!(String::from("ab"), value);
+    assert_eq!((2034, 14), i32::from_data(&reader, index));
+}
+
+#[test]
+pub fn test_varint_unsigned() {
+    assert_eq!(vec![0], 0_u32.to_data_with(VARINT));
+    assert_eq!(vec![127], 127_u64.to_data_with(VARINT));
+    assert_eq!(vec![0xac, 0x02], 300_u16.to_data_with(VARINT));
+    assert_eq!(vec![0xf2, 0x0f], 2034_usize.to_data_with(VARINT));
+    assert_eq!(vec![0xff, 0xff, 0x03], u16::MAX.to_data_with(VARINT));
+    assert_eq!(vec![234], 234_u8.to_data_with(VARINT));
+    let data = u128::MAX.to_data_with(VARINT);
+    assert_eq!(19, data.len());
+    assert_eq!(
+        Ok((u128::MAX, 19)),
+        u128::try_from_data_with(&data, 0, VARINT)
+    );
+    assert_eq!(
+        Ok((300, 2)),
+        u16::try_from_data_with(&[0xac, 0x02], 0, VARINT)
+    );
+    assert_eq!(
+        Ok((u64::MAX, 10)),
+        u64::try_from_data_with(&u64::MAX.to_data_with(VARINT), 0, VARINT)
+    );
+}
+
+#[test]
+pub fn test_varint_signed() {
+    assert_eq!(vec![0], 0_i32.to_data_with(VARINT));
+    assert_eq!(vec![1], (-1_i32).to_data_with(VARINT));
+    assert_eq!(vec![2], 1_i64.to_data_with(VARINT));
+    assert_eq!(vec![127], (-64_i16).to_data_with(VARINT));
+    assert_eq!(vec![0x80, 0x01], 64_i16.to_data_with(VARINT));
+    assert_eq!(vec![0xff, 0xff, 0x03], i16::MIN.to_data_with(VARINT));
+    assert_eq!(vec![3], (-2_isize).to_data_with(VARINT));
+    for value in [i64::MIN, -300, -1, 0, 1, 300, i64::MAX] {
+        let data = value.to_data_with(VARINT);
+        assert_eq!(
+            Ok((value, data.len())),
+            i64::try_from_data_with(&data, 0, VARINT)
+        );
+    }
+    let data = i128::MIN.to_data_with(VARINT);
+    assert_eq!(
+        Ok((i128::MIN, 19)),
+        i128::try_from_data_with(&data, 0, VARINT)
+    );
+}
+
+#[test]
+pub fn test_varint_string() {
+    let value = String::from("This is a test value");
+    let data = value.to_data_with(VARINT);
+    assert_eq!(21, data.len());
+    assert_eq!(20, data[0]);
+    assert_eq!(
+        Ok((value, 21)),
+        String::try_from_data_with(&data, 0, VARINT)
+    );
+
+    let mut value = HashMap::new();
+    value.insert(String::from("KEY1"), -3_i64);
+    let data = value.to_data_with(VARINT);
+    assert_eq!(vec![6, 4, 75, 69, 89, 49, 5], data);
+    assert_eq!(
+        Ok((value, 7)),
+        HashMap::try_from_data_with(&data, 0, VARINT)
+    );
+}
+
+#[test]
+pub fn test_varint_invalid() {
+    let invalid = |type_name| DeserializeError::InvalidVarint { type_name };
+    // Not the shortest form.
+    assert_eq!(
+        invalid("u32"),
+        u32::try_from_data_with(&[0x80, 0x00], 0, VARINT).unwrap_err()
+    );
+    // Too large for the type.
+    assert_eq!(
+        invalid("u16"),
+        u16::try_from_data_with(&[0xff, 0xff, 0x04], 0, VARINT).unwrap_err()
+    );
+    assert_eq!(
+        invalid("i16"),
+        i16::try_from_data_with(&[0xff, 0xff, 0x04], 0, VARINT).unwrap_err()
+    );
+    // Too many bytes for the type.
+    assert_eq!(
+        invalid("u16"),
+        u16::try_from_data_with(&[0x80, 0x80, 0x80, 0x01], 0, VARINT).unwrap_err()
+    );
+    assert_eq!(
+        invalid("u64"),
+        u64::try_from_data_with(
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02],
+            0,
+            VARINT
+        )
+        .unwrap_err()
+    );
+    // Truncated.
+    assert_eq!(
+        Err(DeserializeError::UnexpectedEof {
+            type_name: "u32",
+            needed: 1,
+            available: 0,
+        }),
+        u32::try_from_data_with(&[0x80], 0, VARINT)
+    );
+}
+
+#[test]
+pub fn test_smart_pointers() {
+    let expected = String::from("Value").to_data();
+    assert_eq!(expected, "Value".to_data());
+    assert_eq!(expected, Box::new(String::from("Value")).to_data());
+    assert_eq!(expected, Box::<str>::from("Value").to_data());
+    assert_eq!(expected, Rc::<str>::from("Value").to_data());
+    assert_eq!(expected, Arc::<str>::from("Value").to_data());
+    assert_eq!(expected, Cow::Borrowed("Value").to_data());
+    assert_eq!(expected, Cow::<str>::Owned(String::from("Value")).to_data());
+
+    assert_eq!(
+        (Box::<str>::from("Value"), 13),
+        Box::<str>::from_data(&expected, 0)
+    );
+    assert_eq!(
+        (Rc::<str>::from("Value"), 13),
+        Rc::<str>::from_data(&expected, 0)
+    );
+    assert_eq!(
+        (Arc::<str>::from("Value"), 13),
+        Arc::<str>::from_data(&expected, 0)
+    );
+    assert_eq!(
+        (Box::new(String::from("Value")), 13),
+        Box::from_data(&expected, 0)
+    );
+    let (value, index) = Cow::<str>::from_data(&expected, 0);
+    assert!(matches!(value, Cow::Owned(_)));
+    assert_eq!(("Value", 13), (value.as_ref(), index));
+
+    let list = vec![1_u16, 2];
+    let expected = list.to_data();
+    assert_eq!(expected, list.as_slice().to_data());
+    assert_eq!(expected, list.clone().into_boxed_slice().to_data());
+    assert_eq!(expected, Arc::<[u16]>::from(list.clone()).to_data());
+    assert_eq!(
+        (Box::<[u16]>::from(list.clone()), 12),
+        Box::<[u16]>::from_data(&expected, 0)
+    );
+    assert_eq!(
+        (Rc::<[u16]>::from(list.clone()), 12),
+        Rc::<[u16]>::from_data(&expected, 0)
+    );
+    assert_eq!(
+        (Arc::<[u16]>::from(list.clone()), 12),
+        Arc::<[u16]>::from_data(&expected, 0)
+    );
+    assert_eq!(
+        (Cow::<[u16]>::Owned(list), 12),
+        Cow::<[u16]>::from_data(&expected, 0)
+    );
+    assert_eq!((Rc::new(7_u8), 1), Rc::from_data(&[7], 0));
+    assert_eq!((Arc::new(7_u8), 1), Arc::from_data(&[7], 0));
+}
+
+#[test]
+pub fn test_interior_mutability() {
+    let expected = 2034_u32.to_data();
+    assert_eq!(expected, Cell::new(2034_u32).to_data());
+    assert_eq!(expected, RefCell::new(2034_u32).to_data());
+    assert_eq!(expected, Mutex::new(2034_u32).to_data());
+    assert_eq!(expected, RwLock::new(2034_u32).to_data());
+
+    assert_eq!(2034, Cell::<u32>::from_data(&expected, 0).0.get());
+    assert_eq!(2034, RefCell::<u32>::from_data(&expected, 0).0.into_inner());
+    assert_eq!(
+        2034,
+        Mutex::<u32>::from_data(&expected, 0)
+            .0
+            .into_inner()
+            .unwrap()
+    );
+    assert_eq!(
+        2034,
+        RwLock::<u32>::from_data(&expected, 0)
+            .0
+            .into_inner()
+            .unwrap()
+    );
+}
+
+#[test]
+pub fn test_refcell_borrowed() {
+    let value = RefCell::new(1_u8);
+    let _borrow = value.borrow_mut();
+    let mut data = Vec::new();
+    assert!(value.serialize_into(&mut data).is_err());
+}
+
+#[test]
+pub fn test_poisoned_lock() {
+    let value = Arc::new(Mutex::new(1_u8));
+    let lock = Arc::new(RwLock::new(1_u8));
+    let (value2, lock2) = (value.clone(), lock.clone());
+    let _ = thread::spawn(move || {
+        let mut value = value2.lock().unwrap();
+        let mut lock = lock2.write().unwrap();
+        *value = 2;
+        *lock = 3;
+        panic!("poison the locks");
+    })
+    .join();
+    assert!(value.is_poisoned());
+    assert!(lock.is_poisoned());
+    assert_eq!(vec![2], value.to_data());
+    assert_eq!(vec![3], lock.to_data());
 }